@@ -1,12 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20250516_210859_initial_migration;
+mod m20261016_093000_podcasts;
+mod m20261016_101500_post_status;
+mod m20261016_104500_blog_post_revisions;
+mod m20261016_113000_blog_post_search;
+mod m20261016_121500_rss_feed_format;
+mod m20261016_130000_blog_post_trust_raw_html;
+mod m20261016_140000_blog_post_links;
+mod m20261016_150000_series;
+mod m20261016_160000_pages;
+mod m20261016_170000_multi_blog;
+mod m20261016_180000_authors;
+mod m20261016_190000_api_keys;
+mod m20261016_200000_free_deleted_post_slugs;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20250516_210859_initial_migration::Migration)]
+        vec![
+            Box::new(m20250516_210859_initial_migration::Migration),
+            Box::new(m20261016_093000_podcasts::Migration),
+            Box::new(m20261016_101500_post_status::Migration),
+            Box::new(m20261016_104500_blog_post_revisions::Migration),
+            Box::new(m20261016_113000_blog_post_search::Migration),
+            Box::new(m20261016_121500_rss_feed_format::Migration),
+            Box::new(m20261016_130000_blog_post_trust_raw_html::Migration),
+            Box::new(m20261016_140000_blog_post_links::Migration),
+            Box::new(m20261016_150000_series::Migration),
+            Box::new(m20261016_160000_pages::Migration),
+            Box::new(m20261016_170000_multi_blog::Migration),
+            Box::new(m20261016_180000_authors::Migration),
+            Box::new(m20261016_190000_api_keys::Migration),
+            Box::new(m20261016_200000_free_deleted_post_slugs::Migration),
+        ]
     }
 }
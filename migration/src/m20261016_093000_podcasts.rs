@@ -0,0 +1,113 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Podcasts::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Podcasts::Id))
+                    .col(text(Podcasts::Title))
+                    .col(text(Podcasts::Author))
+                    .col(text(Podcasts::Link))
+                    .col(text(Podcasts::SyndicationUrl))
+                    .col(text(Podcasts::Description))
+                    .col(text(Podcasts::Language))
+                    .col(boolean(Podcasts::Explicit))
+                    .col(text_null(Podcasts::ImageUrl))
+                    .col(text_null(Podcasts::Category))
+                    .col(text_null(Podcasts::OwnerEmail))
+                    .col(timestamp_with_time_zone(Podcasts::LastUpdated))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(PodcastEpisodes::Table)
+                    .if_not_exists()
+                    .col(pk_auto(PodcastEpisodes::Id))
+                    .col(text(PodcastEpisodes::Title))
+                    .col(text(PodcastEpisodes::Slug))
+                    .col(text(PodcastEpisodes::PodcastTitle))
+                    .col(text(PodcastEpisodes::Description))
+                    .col(text(PodcastEpisodes::Text))
+                    .col(array_null(PodcastEpisodes::Hosts, ColumnType::Text))
+                    .col(array_null(PodcastEpisodes::Guests, ColumnType::Text))
+                    .col(text(PodcastEpisodes::AudioUrl))
+                    .col(big_integer(PodcastEpisodes::AudioSize))
+                    .col(text(PodcastEpisodes::AudioType))
+                    .col(array_null(PodcastEpisodes::Tags, ColumnType::Text))
+                    .col(integer(PodcastEpisodes::Length))
+                    .col(integer_null(PodcastEpisodes::Season))
+                    .col(integer_null(PodcastEpisodes::Episode))
+                    .col(boolean(PodcastEpisodes::Explicit))
+                    .col(timestamp_with_time_zone(PodcastEpisodes::Date))
+                    .col(timestamp_with_time_zone(PodcastEpisodes::LastUpdated))
+                    .col(boolean(PodcastEpisodes::Visible))
+                    .col(boolean(PodcastEpisodes::Edited))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PodcastEpisodes::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Podcasts::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Podcasts {
+    Table,
+    Id,
+    Title,
+    Author,
+    Link,
+    SyndicationUrl,
+    Description,
+    Language,
+    Explicit,
+    ImageUrl,
+    Category,
+    OwnerEmail,
+    LastUpdated,
+}
+
+#[derive(DeriveIden)]
+enum PodcastEpisodes {
+    Table,
+    Id,
+    Title,
+    Slug,
+    PodcastTitle,
+    Description,
+    Text,
+    Hosts,
+    Guests,
+    AudioUrl,
+    AudioSize,
+    AudioType,
+    Tags,
+    Length,
+    Season,
+    Episode,
+    Explicit,
+    Date,
+    LastUpdated,
+    Visible,
+    Edited,
+}
@@ -0,0 +1,85 @@
+use crate::{
+    extension::postgres::Type,
+    sea_orm::{DeriveActiveEnum, EnumIter},
+};
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::ActiveEnum};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("post_status"))
+                    .values([
+                        Alias::new("Draft"),
+                        Alias::new("Scheduled"),
+                        Alias::new("Published"),
+                        Alias::new("Deleted"),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .add_column(
+                        ColumnDef::new(BlogPosts::Status)
+                            .custom(PostStatus::name())
+                            .not_null()
+                            .default("Published"),
+                    )
+                    .add_column(timestamp_with_time_zone_null(BlogPosts::PublishAt))
+                    .to_owned(),
+            )
+            .await?;
+        // Posts hidden before statuses existed could only have been hidden by deleting them.
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE blog_posts SET status = 'Deleted' WHERE visible = false")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .drop_column(BlogPosts::Status)
+                    .drop_column(BlogPosts::PublishAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_type(Type::drop().name(Alias::new("post_status")).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    Status,
+    PublishAt,
+}
+
+#[derive(EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "post_status")]
+pub enum PostStatus {
+    #[sea_orm(string_value = "Draft")]
+    Draft,
+    #[sea_orm(string_value = "Scheduled")]
+    Scheduled,
+    #[sea_orm(string_value = "Published")]
+    Published,
+    #[sea_orm(string_value = "Deleted")]
+    Deleted,
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlogPostRevisions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BlogPostRevisions::Id))
+                    .col(integer(BlogPostRevisions::PostId))
+                    .col(integer(BlogPostRevisions::Revision))
+                    .col(text(BlogPostRevisions::Title))
+                    .col(text(BlogPostRevisions::Text))
+                    .col(array_null(BlogPostRevisions::Tags, ColumnType::Text))
+                    .col(timestamp_with_time_zone(BlogPostRevisions::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_blog_post_revisions_post_id")
+                            .from(BlogPostRevisions::Table, BlogPostRevisions::PostId)
+                            .to(BlogPosts::Table, BlogPosts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_blog_post_revisions_post_id_revision")
+                    .table(BlogPostRevisions::Table)
+                    .col(BlogPostRevisions::PostId)
+                    .col(BlogPostRevisions::Revision)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlogPostRevisions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum BlogPostRevisions {
+    Table,
+    Id,
+    PostId,
+    Revision,
+    Title,
+    Text,
+    Tags,
+    CreatedAt,
+}
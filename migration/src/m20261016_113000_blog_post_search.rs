@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Generated columns aren't supported by the schema builder, so this one is raw SQL.
+        // Titles weigh most in ranking, then descriptions, then post text.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE blog_posts ADD COLUMN search_vector tsvector
+                    GENERATED ALWAYS AS (
+                        setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+                        setweight(to_tsvector('english', coalesce(description, '')), 'B') ||
+                        setweight(to_tsvector('english', coalesce(text, '')), 'C')
+                    ) STORED"#,
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX idx_blog_posts_search_vector ON blog_posts USING GIN (search_vector)",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_blog_posts_search_vector")
+                    .table(BlogPosts::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .drop_column(BlogPosts::SearchVector)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    SearchVector,
+}
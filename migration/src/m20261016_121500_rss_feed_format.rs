@@ -0,0 +1,81 @@
+use crate::{
+    extension::postgres::Type,
+    sea_orm::{DeriveActiveEnum, EnumIter},
+};
+use sea_orm_migration::{prelude::*, sea_orm::ActiveEnum};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("feed_format"))
+                    .values([Alias::new("Atom"), Alias::new("Rss")])
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RssFeeds::Table)
+                    .add_column(
+                        ColumnDef::new(RssFeeds::FeedFormat)
+                            .custom(FeedFormat::name())
+                            .not_null()
+                            .default("Atom"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // The podcast feed has always been RSS.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE rss_feeds SET feed_format = 'Rss' WHERE content_type = 'Podcast'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM rss_feeds WHERE content_type = 'Blog' AND feed_format = 'Rss'",
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RssFeeds::Table)
+                    .drop_column(RssFeeds::FeedFormat)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_type(Type::drop().name(Alias::new("feed_format")).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RssFeeds {
+    Table,
+    FeedFormat,
+}
+
+#[derive(EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "feed_format")]
+pub enum FeedFormat {
+    #[sea_orm(string_value = "Atom")]
+    Atom,
+    #[sea_orm(string_value = "Rss")]
+    Rss,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Links existing visible posts to their neighbors in date order. The server keeps them
+        // up to date from here on.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET previous = linked.previous, next = linked.next \
+                 FROM ( \
+                     SELECT id, \
+                         LAG(slug) OVER (ORDER BY date, id) AS previous, \
+                         LEAD(slug) OVER (ORDER BY date, id) AS next \
+                     FROM blog_posts WHERE visible \
+                 ) AS linked \
+                 WHERE blog_posts.id = linked.id",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET previous = NULL, next = NULL WHERE NOT visible",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE blog_posts SET previous = NULL, next = NULL")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Series::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Series::Id))
+                    .col(text(Series::Title))
+                    .col(text_uniq(Series::Slug))
+                    .col(text(Series::Description).default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .add_column(integer_null(BlogPosts::SeriesId))
+                    .add_column(integer_null(BlogPosts::SeriesPosition))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_blog_posts_series_id")
+                            .from_tbl(BlogPosts::Table)
+                            .from_col(BlogPosts::SeriesId)
+                            .to_tbl(Series::Table)
+                            .to_col(Series::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .drop_foreign_key(Alias::new("fk_blog_posts_series_id"))
+                    .drop_column(BlogPosts::SeriesId)
+                    .drop_column(BlogPosts::SeriesPosition)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Series::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    SeriesId,
+    SeriesPosition,
+}
+
+#[derive(DeriveIden)]
+enum Series {
+    Table,
+    Id,
+    Title,
+    Slug,
+    Description,
+}
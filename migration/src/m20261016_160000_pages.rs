@@ -0,0 +1,45 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Pages::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Pages::Id))
+                    .col(text_uniq(Pages::Slug))
+                    .col(text(Pages::Title))
+                    .col(text(Pages::Text))
+                    .col(boolean(Pages::Visible))
+                    .col(timestamp_with_time_zone(Pages::LastUpdated))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Pages::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Pages {
+    Table,
+    Id,
+    Slug,
+    Title,
+    Text,
+    Visible,
+    LastUpdated,
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing blogs get a slug made from their title, or their id if that leaves nothing.
+        // Blogs whose slugs would clash get a numeric suffix, in id order.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogMetadata::Table)
+                    .add_column(text_null(BlogMetadata::Slug))
+                    .to_owned(),
+            )
+            .await?;
+        let db = manager.get_connection();
+        let blogs = db
+            .query_all(Statement::from_string(
+                manager.get_database_backend(),
+                "SELECT id, coalesce(nullif( \
+                     trim(both '-' from lower(regexp_replace(title, '[^a-zA-Z0-9]+', '-', 'g'))), \
+                 ''), id::text) AS slug \
+                 FROM blog_metadata ORDER BY id",
+            ))
+            .await?;
+        let mut used_slugs = HashSet::new();
+        for blog in blogs {
+            let id: i32 = blog.try_get("", "id")?;
+            let base_slug: String = blog.try_get("", "slug")?;
+            let mut slug = base_slug.clone();
+            let mut suffix = 2;
+            while !used_slugs.insert(slug.clone()) {
+                slug = format!("{}-{}", base_slug, suffix);
+                suffix += 1;
+            }
+            db.execute(Statement::from_sql_and_values(
+                manager.get_database_backend(),
+                "UPDATE blog_metadata SET slug = $1 WHERE id = $2",
+                [slug.into(), id.into()],
+            ))
+            .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogMetadata::Table)
+                    .modify_column(ColumnDef::new(BlogMetadata::Slug).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_blog_metadata_slug")
+                    .table(BlogMetadata::Table)
+                    .col(BlogMetadata::Slug)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Posts belonged to the blog whose title they carried, falling back to the first blog.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .add_column(integer_null(BlogPosts::BlogId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET blog_id = blog_metadata.id FROM blog_metadata \
+                 WHERE blog_posts.blog_title = blog_metadata.title",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET blog_id = (SELECT min(id) FROM blog_metadata) \
+                 WHERE blog_id IS NULL",
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .modify_column(ColumnDef::new(BlogPosts::BlogId).integer().not_null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_blog_posts_blog_id")
+                            .from_tbl(BlogPosts::Table)
+                            .from_col(BlogPosts::BlogId)
+                            .to_tbl(BlogMetadata::Table)
+                            .to_col(BlogMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // Posts now only link to their neighbors in the same blog.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET previous = linked.previous, next = linked.next \
+                 FROM ( \
+                     SELECT id, \
+                         LAG(slug) OVER (PARTITION BY blog_id ORDER BY date, id) AS previous, \
+                         LEAD(slug) OVER (PARTITION BY blog_id ORDER BY date, id) AS next \
+                     FROM blog_posts WHERE visible \
+                 ) AS linked \
+                 WHERE blog_posts.id = linked.id",
+            )
+            .await?;
+
+        // Each blog keeps its own feeds. The podcast feed doesn't belong to a blog.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RssFeeds::Table)
+                    .add_column(integer_null(RssFeeds::BlogId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_rss_feeds_blog_id")
+                            .from_tbl(RssFeeds::Table)
+                            .from_col(RssFeeds::BlogId)
+                            .to_tbl(BlogMetadata::Table)
+                            .to_col(BlogMetadata::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE rss_feeds SET blog_id = (SELECT min(id) FROM blog_metadata) \
+                 WHERE content_type = 'Blog'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RssFeeds::Table)
+                    .drop_foreign_key(Alias::new("fk_rss_feeds_blog_id"))
+                    .drop_column(RssFeeds::BlogId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .drop_foreign_key(Alias::new("fk_blog_posts_blog_id"))
+                    .drop_column(BlogPosts::BlogId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_blog_metadata_slug")
+                    .table(BlogMetadata::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogMetadata::Table)
+                    .drop_column(BlogMetadata::Slug)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogMetadata {
+    Table,
+    Id,
+    Slug,
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    BlogId,
+}
+
+#[derive(DeriveIden)]
+enum RssFeeds {
+    Table,
+    BlogId,
+}
@@ -0,0 +1,93 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Authors::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Authors::Id))
+                    .col(text_uniq(Authors::Name))
+                    .col(text_null(Authors::Email))
+                    .col(text_null(Authors::Url))
+                    .col(text(Authors::Bio).default(""))
+                    .col(text_null(Authors::Avatar))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .add_column(integer_null(BlogPosts::AuthorId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_blog_posts_author_id")
+                            .from_tbl(BlogPosts::Table)
+                            .from_col(BlogPosts::AuthorId)
+                            .to_tbl(Authors::Table)
+                            .to_col(Authors::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every name already on a post becomes an author.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO authors (name) SELECT DISTINCT author FROM blog_posts \
+                 ON CONFLICT (name) DO NOTHING",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET author_id = authors.id FROM authors \
+                 WHERE blog_posts.author = authors.name",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BlogPosts::Table)
+                    .drop_foreign_key(Alias::new("fk_blog_posts_author_id"))
+                    .drop_column(BlogPosts::AuthorId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Authors::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlogPosts {
+    Table,
+    AuthorId,
+}
+
+#[derive(DeriveIden)]
+enum Authors {
+    Table,
+    Id,
+    Name,
+    Email,
+    Url,
+    Bio,
+    Avatar,
+}
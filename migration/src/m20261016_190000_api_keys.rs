@@ -0,0 +1,49 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeys::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ApiKeys::Id))
+                    .col(text(ApiKeys::Name))
+                    .col(text_uniq(ApiKeys::KeyHash))
+                    .col(array(ApiKeys::Scopes, ColumnType::Text))
+                    .col(timestamp_with_time_zone(ApiKeys::Created))
+                    .col(timestamp_with_time_zone_null(ApiKeys::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(ApiKeys::LastUsed))
+                    .col(timestamp_with_time_zone_null(ApiKeys::RevokedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKeys::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiKeys {
+    Table,
+    Id,
+    Name,
+    KeyHash,
+    Scopes,
+    Created,
+    ExpiresAt,
+    LastUsed,
+    RevokedAt,
+}
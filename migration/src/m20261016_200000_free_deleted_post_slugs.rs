@@ -0,0 +1,25 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Deleted posts give up their slugs so new posts can use them.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE blog_posts SET slug = slug || '-deleted-' || id \
+                 WHERE status = 'Deleted' AND slug NOT LIKE ('%-deleted-' || id)",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Freed slugs may have been reused since, so they aren't given back.
+        Ok(())
+    }
+}
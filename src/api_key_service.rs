@@ -0,0 +1,239 @@
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::api_keys::{
+    ActiveModel as ApiKeyActive, Column as ApiKeyColumn, Entity as ApiKeyEntity, Model as ApiKey,
+};
+use crate::{
+    server::{full, require_scope, sha256_string, ApiScope},
+    BoxBody, BoxResult,
+};
+
+/// Number of random bytes in a generated key, before hex encoding.
+const KEY_BYTES: usize = 32;
+
+/// Utility struct for our GET /keys handler. Leaves out the key's hash.
+#[derive(Serialize)]
+struct ApiKeyInfo {
+    id: i32,
+    name: String,
+    scopes: Vec<String>,
+    created: String,
+    expires_at: Option<String>,
+    last_used: Option<String>,
+    revoked_at: Option<String>,
+}
+
+impl From<ApiKey> for ApiKeyInfo {
+    fn from(v: ApiKey) -> Self {
+        Self {
+            id: v.id,
+            name: v.name,
+            scopes: v.scopes,
+            created: v.created.to_rfc3339(),
+            expires_at: v.expires_at.map(|d| d.to_rfc3339()),
+            last_used: v.last_used.map(|d| d.to_rfc3339()),
+            revoked_at: v.revoked_at.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+/// Utility struct for our API key write handler function.
+#[derive(Deserialize)]
+struct NewKeyRequest {
+    name: String,
+    scopes: Vec<ApiScope>,
+    expires_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Utility struct for the response to POST /keys, the only time the key itself is returned.
+#[derive(Serialize)]
+struct NewKeyResponse {
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+    key: String,
+}
+
+/// Handler function for GET /keys that returns every API key, newest first, including expired
+/// and revoked ones.
+pub(crate) async fn get_api_keys(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let keys_vec = match ApiKeyEntity::find()
+        .order_by_desc(ApiKeyColumn::Id)
+        .all(db)
+        .await
+    {
+        Ok(k) => k,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let keys_info: Vec<ApiKeyInfo> = keys_vec.into_iter().map(|k| k.into()).collect();
+    let json = serde_json::to_string(&keys_info).expect("Error converting API key vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /keys that generates a new API key with the given scopes. Only
+/// the key's hash is stored, so the response is the only place the key can be read.
+pub(crate) async fn write_api_key(
+    db: &DatabaseConnection,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, &req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let new_key: NewKeyRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(k) => k,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    if new_key.scopes.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(b"Bad request: API key has no scopes".as_slice()))
+            .unwrap());
+    }
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    if new_key.expires_at.is_some_and(|e| e <= now) {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(
+                b"Bad request: expires_at has already passed".as_slice(),
+            ))
+            .unwrap());
+    }
+    let mut key_bytes = [0u8; KEY_BYTES];
+    getrandom::getrandom(&mut key_bytes)?;
+    let key: String = key_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let mut scopes: Vec<String> = new_key
+        .scopes
+        .iter()
+        .map(|s| s.as_str().to_owned())
+        .collect();
+    scopes.sort();
+    scopes.dedup();
+    let key_active = ApiKeyActive {
+        id: NotSet,
+        name: Set(new_key.name),
+        key_hash: Set(sha256_string(key.as_bytes())),
+        scopes: Set(scopes),
+        created: Set(now),
+        expires_at: Set(new_key.expires_at),
+        last_used: Set(None),
+        revoked_at: Set(None),
+    };
+    let key_returned = match key_active.insert(db).await {
+        Ok(k) => k,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let key_response = NewKeyResponse {
+        info: key_returned.into(),
+        key,
+    };
+    let json = serde_json::to_string(&key_response).expect("Error converting API key to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for DELETE /keys/[id] that revokes the API key. Revoked keys are kept so
+/// they still show up in GET /keys.
+pub(crate) async fn revoke_api_key(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
+    let id = match path_vec[3].parse::<i32>() {
+        Ok(i) if path_vec.len() == 4 => i,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("Content-Type", "text/plain")
+                .body(full(
+                    b"Bad request: URL should be in format '/api/keys/[id]'".as_slice(),
+                ))
+                .unwrap());
+        }
+    };
+    let api_key = match ApiKeyEntity::find_by_id(id).one(db).await {
+        Ok(Some(k)) if k.revoked_at.is_none() => k,
+        Ok(_) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap());
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let mut key_active: ApiKeyActive = api_key.into();
+    key_active.revoked_at = Set(Some(
+        FixedOffset::east_opt(0)
+            .unwrap()
+            .from_utc_datetime(&Utc::now().naive_utc()),
+    ));
+    if let Err(e) = key_active.update(db).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    let success_string = format!("API key successfully revoked: {}", id);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
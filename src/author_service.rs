@@ -0,0 +1,462 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Method, Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, NotSet, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::blog_atom::generate_filtered_atom_feed;
+use crate::blog_service::{deserialize_some, update_blog_rss, BlogPostInfo};
+use crate::entity::authors::{
+    ActiveModel as AuthorActive, Column as AuthorColumn, Entity as AuthorEntity, Model as Author,
+};
+use crate::entity::blog_posts::{Column as BlogPostColumn, Entity as BlogPostEntity};
+use crate::entity::sea_orm_active_enums::PostStatus;
+use crate::{
+    server::{api_key_auth, full, require_scope, ApiScope},
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our author edit handler function. `email`, `url` and `avatar` can be
+/// cleared with an explicit `null`.
+#[derive(Deserialize)]
+struct AuthorEditRequest {
+    name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    url: Option<Option<String>>,
+    bio: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    avatar: Option<Option<String>>,
+}
+
+/// Routing function for everything under /api/authors/[id].
+pub(crate) async fn handle_author_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let Ok(id) = path_vec[3].parse::<i32>() else {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/authors/[id]'".as_slice(),
+            ))
+            .unwrap());
+    };
+    match (req.method(), &path_vec[4..]) {
+        (&Method::GET, []) => get_author(&ctx.db, &req, id).await,
+        (&Method::PUT, []) => edit_author(ctx, req, id).await,
+        (&Method::GET, ["posts"]) => get_author_posts(&ctx.db, &req, id).await,
+        (&Method::GET, ["atom"]) => get_author_atom(&ctx.db, id, &path).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /authors that returns every author, ordered by name. Emails are
+/// only included for authenticated callers.
+pub(crate) async fn get_authors(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let mut authors_vec = match AuthorEntity::find()
+        .order_by_asc(AuthorColumn::Name)
+        .all(db)
+        .await
+    {
+        Ok(a) => a,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if !authenticated {
+        authors_vec.iter_mut().for_each(hide_email);
+    }
+    let json = serde_json::to_string(&authors_vec).expect("Error converting author vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /authors that adds an author. Posts are credited to them through
+/// their `author_id`.
+pub(crate) async fn write_author(
+    db: &DatabaseConnection,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let author: Author = match serde_json::from_reader(whole_body.reader()) {
+        Ok(a) => a,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    if author.name.trim().is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(b"Bad request: Author name is empty".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = check_duplicate_name(db, &author.name).await {
+        return Ok(r);
+    }
+    let mut author_active: AuthorActive = author.into();
+    // Let the database assign the id rather than inserting the deserialized default.
+    author_active.id = NotSet;
+    let author_returned = match author_active.insert(db).await {
+        Ok(a) => a,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let json = serde_json::to_string(&author_returned).expect("Error converting author to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /authors/[id] that returns the author's profile. Their email is
+/// only included for authenticated callers.
+async fn get_author(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    id: i32,
+) -> BoxResult<Response<BoxBody>> {
+    let mut author = match find_author(db, id).await {
+        Ok(a) => a,
+        Err(r) => return Ok(r),
+    };
+    if !api_key_auth(db, req, ApiScope::ReadDrafts).await {
+        hide_email(&mut author);
+    }
+    let json = serde_json::to_string(&author).expect("Error converting author to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for PUT /authors/[id]. A new name is copied to the author's posts, which
+/// are marked updated, and the feeds of every blog they've written in are regenerated since
+/// their entries carry the author's details.
+async fn edit_author(
+    ctx: &Context,
+    req: Request<Incoming>,
+    id: i32,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let edits: AuthorEditRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(e) => e,
+        Err(e) => {
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    let author = match find_author(&ctx.db, id).await {
+        Ok(a) => a,
+        Err(r) => return Ok(r),
+    };
+    let name_changed = edits.name.as_ref().is_some_and(|n| *n != author.name);
+    let mut author_active: AuthorActive = author.into();
+    if let Some(name) = edits.name {
+        if name.trim().is_empty() {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(b"Bad request: Author name is empty".as_slice()))
+                .unwrap());
+        }
+        if name_changed && let Some(r) = check_duplicate_name(&ctx.db, &name).await {
+            return Ok(r);
+        }
+        author_active.name = Set(name);
+    }
+    if let Some(email) = edits.email {
+        author_active.email = Set(email);
+    }
+    if let Some(url) = edits.url {
+        author_active.url = Set(url);
+    }
+    if let Some(bio) = edits.bio {
+        author_active.bio = Set(bio);
+    }
+    if let Some(avatar) = edits.avatar {
+        author_active.avatar = Set(avatar);
+    }
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let author_returned = match author_active.update(&txn).await {
+        Ok(a) => a,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    // Posts are marked updated too, so their validators change and they're rendered again.
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    if name_changed
+        && let Err(e) = BlogPostEntity::update_many()
+            .col_expr(BlogPostColumn::Author, Expr::value(&author_returned.name))
+            .col_expr(BlogPostColumn::LastUpdated, Expr::value(now))
+            .filter(BlogPostColumn::AuthorId.eq(id))
+            .exec(&txn)
+            .await
+    {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    let blog_ids = match BlogPostEntity::find()
+        .select_only()
+        .column(BlogPostColumn::BlogId)
+        .distinct()
+        .filter(BlogPostColumn::AuthorId.eq(id))
+        .into_tuple::<i32>()
+        .all(&*ctx.db)
+        .await
+    {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    for blog_id in blog_ids {
+        if let Some(r) = update_blog_rss(ctx, blog_id).await {
+            return Ok(r);
+        }
+    }
+    let json = serde_json::to_string(&author_returned).expect("Error converting author to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /authors/[id]/posts that returns the author's posts, newest first,
+/// in the same format as GET /posts. Drafts and scheduled posts are only included for
+/// authenticated callers.
+async fn get_author_posts(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    id: i32,
+) -> BoxResult<Response<BoxBody>> {
+    if let Err(r) = find_author(db, id).await {
+        return Ok(r);
+    }
+    let mut query = BlogPostEntity::find().filter(BlogPostColumn::AuthorId.eq(id));
+    query = if api_key_auth(db, req, ApiScope::ReadDrafts).await {
+        query.filter(BlogPostColumn::Status.ne(PostStatus::Deleted))
+    } else {
+        query.filter(BlogPostColumn::Visible.eq(true))
+    };
+    let posts_vec = match query.order_by_desc(BlogPostColumn::Date).all(db).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let posts_info: Vec<BlogPostInfo> = posts_vec.into_iter().map(|p| p.into()).collect();
+    let json =
+        serde_json::to_string(&posts_info).expect("Error converting blog post info vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /authors/[id]/atom that returns an Atom feed of the author's posts.
+/// Generated on request rather than cached like the main feed.
+async fn get_author_atom(
+    db: &DatabaseConnection,
+    id: i32,
+    path: &str,
+) -> BoxResult<Response<BoxBody>> {
+    if let Err(r) = find_author(db, id).await {
+        return Ok(r);
+    }
+    let condition = Condition::all().add(BlogPostColumn::AuthorId.eq(id));
+    let feed = match generate_filtered_atom_feed(db, None, condition, Some(path)).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Error generating Atom feed".as_slice()))
+                .unwrap());
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/atom+xml")
+        .body(full(feed.to_string()))
+        .unwrap())
+}
+
+/// Finds the author a post is credited to: the one with `author_id` if it's given, or else the
+/// one called `name`, who's added to `authors` if they aren't there yet. Returns `None` if
+/// there's no author with the id, or no id or name to go on.
+pub(crate) async fn resolve_author<C: ConnectionTrait>(
+    db: &C,
+    author_id: Option<i32>,
+    name: &str,
+) -> Result<Option<Author>, DbErr> {
+    if let Some(id) = author_id {
+        return AuthorEntity::find_by_id(id).one(db).await;
+    }
+    if name.trim().is_empty() {
+        return Ok(None);
+    }
+    if let Some(a) = AuthorEntity::find()
+        .filter(AuthorColumn::Name.eq(name))
+        .one(db)
+        .await?
+    {
+        return Ok(Some(a));
+    }
+    let author_active = AuthorActive {
+        id: NotSet,
+        name: Set(name.to_owned()),
+        email: Set(None),
+        url: Set(None),
+        bio: Set(String::new()),
+        avatar: Set(None),
+    };
+
+    author_active.insert(db).await.map(Some)
+}
+
+/// Clears an author's email so it isn't returned to callers without an API key.
+fn hide_email(author: &mut Author) {
+    author.email = None;
+}
+
+async fn check_duplicate_name(db: &DatabaseConnection, name: &str) -> Option<Response<BoxBody>> {
+    match AuthorEntity::find()
+        .filter(AuthorColumn::Name.eq(name))
+        .one(db)
+        .await
+    {
+        Ok(None) => None,
+        Ok(Some(_)) => Some(
+            Response::builder()
+                .status(StatusCode::CONFLICT)
+                .body(full(b"Error: Duplicate author name".as_slice()))
+                .unwrap(),
+        ),
+        Err(e) => {
+            error!("{}", e);
+            Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap(),
+            )
+        }
+    }
+}
+
+async fn find_author(db: &DatabaseConnection, id: i32) -> Result<Author, Response<BoxBody>> {
+    match AuthorEntity::find_by_id(id).one(db).await {
+        Ok(Some(a)) => Ok(a),
+        Ok(None) => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+        Err(e) => {
+            error!("{}", e);
+            Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap())
+        }
+    }
+}
@@ -1,39 +1,121 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock, RwLock},
+};
+
 use anyhow::anyhow;
-use atom_syndication::{extension::ExtensionMap, Content, Entry, Feed, FeedBuilder, Link, Person};
+use atom_syndication::{
+    extension::ExtensionMap, Category, Content, Entry, Feed, FeedBuilder, Link, Person, Text,
+};
 use chrono::Utc;
-use pulldown_cmark::{html::push_html, Options, Parser};
-use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder};
+use hyper::body::Bytes;
+use pulldown_cmark::{html::push_html, Event, Options, Parser, Tag, TagEnd};
+use rss::{extension::dublincore::DublinCoreExtension, Channel, ChannelBuilder, Guid, Item};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder,
+};
 
-use crate::entity::blog_metadata::Entity as BlogMetaEntity;
+use serde::Serialize;
+
+use crate::blog_json_feed::JsonFeed;
+use crate::entity::authors::{Entity as AuthorEntity, Model as Author};
+use crate::entity::blog_metadata::{
+    Column as BlogMetaColumn, Entity as BlogMetaEntity, Model as BlogMeta,
+};
 use crate::entity::blog_posts::{
     Column as BlogPostColumn, Entity as BlogPostEntity, Model as BlogPost,
 };
-use crate::{BoxResult, BASE_URL};
-
-/// Generates new Atom feed. Run on write operations for the blog. Depends on the database
-/// having a single-row "blog_metadata" table for now.
-pub(crate) async fn generate_atom_feed(db: &DatabaseConnection) -> BoxResult<Feed> {
-    let maybe_blog_metadata = match BlogMetaEntity::find().one(db).await {
-        Ok(m) => m,
-        Err(e) => return Err(Box::new(e)),
-    };
-    let blog_metadata = match maybe_blog_metadata {
-        Some(m) => m,
-        None => return Err(anyhow!("Blog metadata not in database.").into()),
-    };
-    let author = Person {
-        name: blog_metadata.author.clone(),
-        email: blog_metadata.author_email.clone(),
-        uri: blog_metadata.author_url.clone(),
+use crate::entity::sea_orm_active_enums::PostStatus;
+use crate::highlight::highlight_code_blocks;
+use crate::math::render_math;
+use crate::sanitize::sanitize_html;
+use crate::shortcode::expand_shortcodes;
+use crate::BoxResult;
+
+/// A serialized feed as stored in `rss_feeds`, kept with the time it was last regenerated so
+/// requests can be answered and validated without serializing the feed again. `Bytes` makes
+/// handing the body to a response a cheap reference count bump.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CachedFeed {
+    pub(crate) xml: Bytes,
+    pub(crate) last_updated: DateTimeWithTimeZone,
+}
+
+impl CachedFeed {
+    pub(crate) fn etag(&self) -> String {
+        format!("\"{:x}\"", self.last_updated.timestamp_micros())
+    }
+}
+
+/// A blog's feeds in every format, kept in `Context` by blog id. The blog's slug is kept with
+/// them to serve its feed routes without a database lookup.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BlogFeeds {
+    pub(crate) slug: String,
+    pub(crate) atom: CachedFeed,
+    pub(crate) rss: Channel,
+    pub(crate) json: JsonFeed,
+}
+
+/// The blog served at the routes without a blog in them, e.g. /api/atom: the first one
+/// configured.
+pub(crate) async fn default_blog(db: &DatabaseConnection) -> BoxResult<BlogMeta> {
+    match BlogMetaEntity::find()
+        .order_by_asc(BlogMetaColumn::Id)
+        .one(db)
+        .await?
+    {
+        Some(m) => Ok(m),
+        None => Err(anyhow!("Blog metadata not in database.").into()),
+    }
+}
+
+/// Generates new Atom feed of the blog's published posts. Run on write operations for the blog.
+pub(crate) async fn generate_atom_feed(
+    db: &DatabaseConnection,
+    blog_metadata: &BlogMeta,
+) -> BoxResult<Feed> {
+    let condition = Condition::all().add(BlogPostColumn::BlogId.eq(blog_metadata.id));
+    generate_filtered_atom_feed(db, Some(blog_metadata), condition, None).await
+}
+
+/// Generates an Atom feed of the published posts matching `condition`, e.g. for a single tag.
+/// `self_path` is the path the feed is served at, resolved against the blog's syndication URL
+/// for the feed's self link. The main feed, with no path, links to the syndication URL itself.
+/// Feeds that aren't for one blog, like a tag's, can span blogs, so they don't carry any blog's
+/// author and their entries link to each post's own blog. Entries carry their author's details
+/// from `authors`.
+pub(crate) async fn generate_filtered_atom_feed(
+    db: &DatabaseConnection,
+    blog_metadata: Option<&BlogMeta>,
+    condition: Condition,
+    self_path: Option<&str>,
+) -> BoxResult<Feed> {
+    let blog_urls: HashMap<i32, String> = BlogMetaEntity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|b| (b.id, b.blog_url))
+        .collect();
+    let syndication_url = match blog_metadata {
+        Some(m) => m.syndication_url.clone(),
+        None => default_blog(db).await?.syndication_url,
     };
     let posts_vec = BlogPostEntity::find()
+        .find_also_related(AuthorEntity)
+        .filter(BlogPostColumn::Status.eq(PostStatus::Published))
+        .filter(condition)
         .order_by_desc(BlogPostColumn::Date)
         .all(db)
         .await?
-        .into_iter()
-        .filter(|p| p.visible);
+        .into_iter();
+    let self_href = match self_path {
+        Some(path) => format!("{}{}", url_origin(&syndication_url), path),
+        None => syndication_url,
+    };
     let self_link = Link {
-        href: blog_metadata.syndication_url.clone(),
+        href: self_href,
         rel: "self".to_string(),
         hreflang: Some("English".to_string()),
         mime_type: Some("application/atom+xml".to_string()),
@@ -41,10 +123,25 @@ pub(crate) async fn generate_atom_feed(db: &DatabaseConnection) -> BoxResult<Fee
         length: None,
     };
     let last_updated = Utc::now();
-    let entries: Vec<Entry> = posts_vec.map(Entry::from).collect();
+    let entries: Vec<Entry> = posts_vec
+        .map(|(post, author)| {
+            let blog_url = blog_urls.get(&post.blog_id).map_or("", String::as_str);
+            let mut entry = post_entry(post, blog_url);
+            if let Some(a) = author {
+                entry.authors = vec![Person::from(a)];
+            }
+            entry
+        })
+        .collect();
     let mut feed_builder = FeedBuilder::default();
+    if let Some(m) = blog_metadata {
+        feed_builder.author(Person {
+            name: m.author.clone(),
+            email: m.author_email.clone(),
+            uri: m.author_url.clone(),
+        });
+    }
     let feed = feed_builder
-        .author(author)
         .lang("English".to_string())
         .link(self_link)
         .updated(last_updated)
@@ -54,11 +151,285 @@ pub(crate) async fn generate_atom_feed(db: &DatabaseConnection) -> BoxResult<Fee
     Ok(feed)
 }
 
+/// Generates new RSS 2.0 feed with the same posts as the blog's Atom feed, for readers that
+/// don't support Atom. Run on write operations for the blog.
+pub(crate) async fn generate_rss_feed(
+    db: &DatabaseConnection,
+    blog_metadata: &BlogMeta,
+) -> BoxResult<Channel> {
+    let items: Vec<Item> = BlogPostEntity::find()
+        .filter(BlogPostColumn::Status.eq(PostStatus::Published))
+        .filter(BlogPostColumn::BlogId.eq(blog_metadata.id))
+        .order_by_desc(BlogPostColumn::Date)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| post_item(p, &blog_metadata.blog_url))
+        .collect();
+    let mut channel_builder = ChannelBuilder::default();
+    let channel = channel_builder
+        .title(blog_metadata.title.clone())
+        .link(blog_metadata.blog_url.clone())
+        .description(blog_metadata.title.clone())
+        .language(Some("en".to_string()))
+        .managing_editor(blog_metadata.author_email.clone())
+        .last_build_date(Some(Utc::now().to_rfc2822()))
+        .items(items)
+        .build();
+
+    Ok(channel)
+}
+
+/// A post's URL: the blog's URL followed by the post's slug.
+pub(crate) fn post_url(blog_url: &str, slug: &str) -> String {
+    format!("{}/{}", blog_url.trim_end_matches('/'), slug)
+}
+
+/// Returns the scheme and host part of a URL, e.g. "https://example.com" for
+/// "https://example.com/api/atom".
+pub(crate) fn url_origin(url: &str) -> &str {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    match url[host_start..].find('/') {
+        Some(i) => &url[..host_start + i],
+        None => url,
+    }
+}
+
+/// A post's markdown rendered to HTML with its table of contents, along with the post's
+/// `last_updated` time and `trust_raw_html` setting it was rendered for.
+#[derive(Debug)]
+pub(crate) struct RenderedPost {
+    pub(crate) html: String,
+    pub(crate) toc: Vec<TocEntry>,
+    /// The start of the post as plain text, standing in for its description if it has none.
+    pub(crate) excerpt: String,
+    pub(crate) word_count: usize,
+    /// Estimated minutes to read the post, rounded up.
+    pub(crate) reading_time: usize,
+    rendered_for: (DateTimeWithTimeZone, bool),
+}
+
+/// Reading speed used to estimate reading times, in words per minute.
+const WORDS_PER_MINUTE: usize = 200;
+/// Longest generated description, in characters.
+const EXCERPT_LENGTH: usize = 160;
+
+/// A heading in a post's table of contents. `id` is the heading's anchor in the rendered HTML.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct TocEntry {
+    level: u8,
+    text: String,
+    id: String,
+}
+
+/// Rendered posts by id.
+static RENDER_CACHE: LazyLock<RwLock<HashMap<i32, Arc<RenderedPost>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Returns the post's rendered markdown, only rendering it if it's changed since it was last
+/// rendered. Every write regenerates the feeds, so changed posts are rendered once there and
+/// reads and later feed regeneration use the cached HTML.
+pub(crate) fn render_post(post: &BlogPost) -> Arc<RenderedPost> {
+    if let Some(rendered) = RENDER_CACHE
+        .read()
+        .expect("Error reading render cache RwLock")
+        .get(&post.id)
+        && rendered.rendered_for == (post.last_updated, post.trust_raw_html)
+    {
+        return rendered.clone();
+    }
+    let (mut html, toc, plain_text) = render_markdown(&post.text);
+    if !post.trust_raw_html {
+        html = sanitize_html(&html);
+    }
+    let word_count = plain_text.split_whitespace().count();
+    let rendered = Arc::new(RenderedPost {
+        html,
+        toc,
+        excerpt: excerpt(&plain_text),
+        word_count,
+        reading_time: word_count.div_ceil(WORDS_PER_MINUTE).max(1),
+        rendered_for: (post.last_updated, post.trust_raw_html),
+    });
+    RENDER_CACHE
+        .write()
+        .expect("Error writing render cache RwLock")
+        .insert(post.id, rendered.clone());
+
+    rendered
+}
+
+/// Renders a static page's or podcast episode's markdown to sanitized HTML along with its table
+/// of contents. These are rarely read, so unlike posts they aren't cached.
+pub(crate) fn render_page(text: &str) -> (String, Vec<TocEntry>) {
+    let (html, toc, _) = render_markdown(text);
+
+    (sanitize_html(&html), toc)
+}
+
+/// The post's description, or an excerpt from its text if it doesn't have one.
+pub(crate) fn post_description(post: &BlogPost) -> String {
+    if post.description.trim().is_empty() {
+        render_post(post).excerpt.clone()
+    } else {
+        post.description.clone()
+    }
+}
+
+/// Generates a description from a post's markdown for posts written without one.
+pub(crate) fn generate_description(text: &str) -> String {
+    let events = Parser::new_ext(text, get_markdown_options()).collect::<Vec<Event>>();
+    excerpt(&markdown_plain_text(&events))
+}
+
+/// Returns the rendered HTML, the table of contents and the text without markup.
+fn render_markdown(text: &str) -> (String, Vec<TocEntry>, String) {
+    let parser = Parser::new_ext(text, get_markdown_options());
+    let (events, toc) = anchor_headings(parser.collect());
+    let plain_text = markdown_plain_text(&events);
+
+    (render_events(events.into_iter()), toc, plain_text)
+}
+
+/// The prose in a markdown event stream, with whitespace collapsed. Headings, code blocks,
+/// which include shortcodes, math and raw HTML are left out.
+fn markdown_plain_text(events: &[Event<'_>]) -> String {
+    let mut text = String::new();
+    let mut skip_depth = 0;
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading { .. } | Tag::CodeBlock(_)) => skip_depth += 1,
+            Event::End(TagEnd::Heading(_) | TagEnd::CodeBlock) => skip_depth -= 1,
+            Event::Text(t) | Event::Code(t) if skip_depth == 0 => text.push_str(t),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::TableCell) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Cuts plain text down to `EXCERPT_LENGTH` characters at a word boundary.
+fn excerpt(plain_text: &str) -> String {
+    if plain_text.chars().count() <= EXCERPT_LENGTH {
+        return plain_text.to_owned();
+    }
+    let mut excerpt = String::with_capacity(EXCERPT_LENGTH + 3);
+    for word in plain_text.split(' ') {
+        // Leaves room for the separating space and the ellipsis.
+        if excerpt.chars().count() + word.chars().count() + 2 > EXCERPT_LENGTH {
+            break;
+        }
+        if !excerpt.is_empty() {
+            excerpt.push(' ');
+        }
+        excerpt.push_str(word);
+    }
+    if excerpt.is_empty() {
+        // A single word longer than the limit.
+        excerpt = plain_text.chars().take(EXCERPT_LENGTH - 1).collect();
+    }
+    excerpt.push('…');
+
+    excerpt
+}
+
+/// Renders markdown nested in a shortcode. Its headings aren't anchored or added to the post's
+/// table of contents.
+pub(crate) fn render_markdown_fragment(text: &str) -> String {
+    render_events(Parser::new_ext(text, get_markdown_options()))
+}
+
+fn render_events<'a>(events: impl Iterator<Item = Event<'a>>) -> String {
+    let mut parsed_html = String::with_capacity(2048);
+    push_html(
+        &mut parsed_html,
+        render_math(highlight_code_blocks(expand_shortcodes(events))),
+    );
+
+    parsed_html
+}
+
+/// Gives every heading without an explicit `{#id}` one slugified from its text, and collects
+/// the headings into a table of contents. Colliding ids get a numeric suffix in document order,
+/// so a heading's anchor only changes if the headings before it do.
+fn anchor_headings(mut events: Vec<Event<'_>>) -> (Vec<Event<'_>>, Vec<TocEntry>) {
+    let mut used_ids: HashSet<String> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+    let mut toc = Vec::new();
+    let mut heading_start: Option<(usize, String)> = None;
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { .. }) => heading_start = Some((i, String::new())),
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, text)) = heading_start.as_mut() {
+                    text.push_str(t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((start, text)) = heading_start.take() else {
+                    continue;
+                };
+                let Event::Start(Tag::Heading { level, id, .. }) = &mut events[start] else {
+                    continue;
+                };
+                let heading_id = match id {
+                    Some(i) => i.to_string(),
+                    None => {
+                        let slug = slugify(&text);
+                        let mut unique = slug.clone();
+                        let mut n = 1;
+                        while used_ids.contains(&unique) {
+                            unique = format!("{}-{}", slug, n);
+                            n += 1;
+                        }
+                        used_ids.insert(unique.clone());
+                        *id = Some(unique.clone().into());
+                        unique
+                    }
+                };
+                toc.push(TocEntry {
+                    level: *level as u8,
+                    text: text.trim().to_owned(),
+                    id: heading_id,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    (events, toc)
+}
+
+/// Lowercases heading text and joins its words with hyphens for use as an anchor id, e.g.
+/// "Hello, World!" becomes "hello-world".
+fn slugify(text: &str) -> String {
+    let slug = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("-");
+    if slug.is_empty() {
+        "section".to_owned()
+    } else {
+        slug
+    }
+}
+
 pub(crate) fn get_markdown_options() -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_FOOTNOTES);
     options.insert(Options::ENABLE_GFM);
     options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options.insert(Options::ENABLE_MATH);
     options.insert(Options::ENABLE_SMART_PUNCTUATION);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_SUBSCRIPT);
@@ -67,40 +438,140 @@ pub(crate) fn get_markdown_options() -> Options {
     options
 }
 
-impl From<BlogPost> for Entry {
-    fn from(p: BlogPost) -> Self {
-        let post_url = format!("{}{}", BASE_URL.get().unwrap(), &p.slug);
-        let author = Person {
-            name: p.author.clone(),
+/// Authors' emails are only shown to callers with an API key, so they're left out of feeds.
+impl From<Author> for Person {
+    fn from(a: Author) -> Self {
+        Person {
+            name: a.name,
             email: None,
-            uri: None,
-        };
-        let link = Link {
-            href: post_url.clone(),
-            ..Default::default()
-        };
-        let md_options = get_markdown_options();
-        let mut parsed_html = String::with_capacity(2048);
-        let parser = Parser::new_ext(&p.text, md_options);
-        push_html(&mut parsed_html, parser);
-        let mut content = Content::default();
-        content.set_content_type("text/html".to_string());
-        content.set_value(parsed_html);
-
-        Entry {
-            title: p.title.clone().into(),
-            id: post_url,
-            updated: p.last_updated,
-            authors: vec![author],
-            categories: Vec::new(),
-            contributors: Vec::new(),
-            links: vec![link],
-            published: Some(p.date),
-            rights: None,
-            source: None,
-            summary: None,
-            content: Some(content),
-            extensions: ExtensionMap::new(),
+            uri: a.url,
         }
     }
 }
+
+/// Builds a post's Atom entry, linking to it under its blog's URL.
+fn post_entry(p: BlogPost, blog_url: &str) -> Entry {
+    let post_url = post_url(blog_url, &p.slug);
+    let author = Person {
+        name: p.author.clone(),
+        email: None,
+        uri: None,
+    };
+    let link = Link {
+        href: post_url.clone(),
+        ..Default::default()
+    };
+    let parsed_html = render_post(&p).html.clone();
+    let categories = p
+        .tags
+        .iter()
+        .flatten()
+        .map(|t| Category {
+            term: t.clone(),
+            scheme: None,
+            label: None,
+        })
+        .collect();
+    let mut content = Content::default();
+    content.set_content_type("text/html".to_string());
+    content.set_value(parsed_html);
+
+    Entry {
+        title: p.title.clone().into(),
+        id: post_url,
+        updated: p.last_updated,
+        authors: vec![author],
+        categories,
+        contributors: Vec::new(),
+        links: vec![link],
+        published: Some(p.date),
+        rights: None,
+        source: None,
+        summary: Some(post_description(&p))
+            .filter(|d| !d.is_empty())
+            .map(Text::plain),
+        content: Some(content),
+        extensions: ExtensionMap::new(),
+    }
+}
+
+/// Builds a post's RSS item, linking to it under its blog's URL.
+fn post_item(p: BlogPost, blog_url: &str) -> Item {
+    let post_url = post_url(blog_url, &p.slug);
+    let guid = Guid {
+        value: post_url.clone(),
+        permalink: true,
+    };
+    let categories = p
+        .tags
+        .iter()
+        .flatten()
+        .map(|t| rss::Category {
+            name: t.clone(),
+            domain: None,
+        })
+        .collect();
+    // RSS wants an email address for the author, so the name goes in dc:creator instead.
+    let dublin_core_ext = DublinCoreExtension {
+        creators: vec![p.author.clone()],
+        ..Default::default()
+    };
+
+    Item {
+        title: Some(p.title.clone()),
+        link: Some(post_url),
+        description: Some(post_description(&p)),
+        categories,
+        guid: Some(guid),
+        pub_date: Some(p.date.to_rfc2822()),
+        content: Some(render_post(&p).html.clone()),
+        dublin_core_ext: Some(dublin_core_ext),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toc(markdown: &str) -> Vec<(u8, String, String)> {
+        let events = Parser::new_ext(markdown, get_markdown_options()).collect();
+        let (_, toc) = anchor_headings(events);
+        toc.into_iter().map(|e| (e.level, e.text, e.id)).collect()
+    }
+
+    #[test]
+    fn slugifies_heading_text() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Ünïcode  Heading 2 "), "ünïcode-heading-2");
+        assert_eq!(slugify("?!"), "section");
+    }
+
+    #[test]
+    fn anchors_headings_with_unique_ids() {
+        assert_eq!(
+            toc("# Intro\n\n## Intro\n\n### Setup {#intro-1}\n\n## `code` and text\n"),
+            vec![
+                (1, "Intro".to_owned(), "intro".to_owned()),
+                (2, "Intro".to_owned(), "intro-2".to_owned()),
+                (3, "Setup".to_owned(), "intro-1".to_owned()),
+                (2, "code and text".to_owned(), "code-and-text".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_short_text_whole() {
+        assert_eq!(excerpt("A short post."), "A short post.");
+    }
+
+    #[test]
+    fn cuts_long_text_at_a_word() {
+        let text = "word ".repeat(50);
+        let cut = excerpt(text.trim());
+        assert!(cut.chars().count() <= EXCERPT_LENGTH);
+        assert!(cut.ends_with("word…"));
+        let long_word = "a".repeat(EXCERPT_LENGTH * 2);
+        assert_eq!(excerpt(&long_word).chars().count(), EXCERPT_LENGTH);
+    }
+}
@@ -0,0 +1,112 @@
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+
+use crate::blog_atom::{post_description, post_url, render_post, url_origin};
+use crate::entity::blog_metadata::Model as BlogMeta;
+use crate::entity::blog_posts::{
+    Column as BlogPostColumn, Entity as BlogPostEntity, Model as BlogPost,
+};
+use crate::entity::sea_orm_active_enums::PostStatus;
+use crate::BoxResult;
+
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// A JSON Feed 1.1 document. See https://www.jsonfeed.org/version/1.1/.
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct JsonFeed {
+    version: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    home_page_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feed_url: Option<String>,
+    authors: Vec<JsonFeedAuthor>,
+    language: String,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct JsonFeedAuthor {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    title: String,
+    content_html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    date_published: String,
+    date_modified: String,
+    authors: Vec<JsonFeedAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// Generates new JSON feed from the same data as the blog's Atom feed. Run on write operations
+/// for the blog. Its `feed_url` is the blog's own route, which also works for the blog served at
+/// /api/feed.json.
+pub(crate) async fn generate_json_feed(
+    db: &DatabaseConnection,
+    blog_metadata: &BlogMeta,
+) -> BoxResult<JsonFeed> {
+    let author = JsonFeedAuthor {
+        name: blog_metadata.author.clone(),
+        url: blog_metadata.author_url.clone(),
+    };
+    let items: Vec<JsonFeedItem> = BlogPostEntity::find()
+        .filter(BlogPostColumn::Status.eq(PostStatus::Published))
+        .filter(BlogPostColumn::BlogId.eq(blog_metadata.id))
+        .order_by_desc(BlogPostColumn::Date)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| JsonFeedItem::new(p, &blog_metadata.blog_url))
+        .collect();
+    let feed_url = format!(
+        "{}/api/blogs/{}/feed.json",
+        url_origin(&blog_metadata.syndication_url),
+        blog_metadata.slug
+    );
+
+    Ok(JsonFeed {
+        version: JSON_FEED_VERSION.to_string(),
+        title: blog_metadata.title.clone(),
+        home_page_url: Some(blog_metadata.blog_url.clone()),
+        feed_url: Some(feed_url),
+        authors: vec![author],
+        language: "en".to_string(),
+        items,
+    })
+}
+
+impl JsonFeedItem {
+    /// Builds a post's feed item, linking to it under its blog's URL.
+    fn new(p: BlogPost, blog_url: &str) -> Self {
+        let post_url = post_url(blog_url, &p.slug);
+        let author = JsonFeedAuthor {
+            name: p.author.clone(),
+            url: None,
+        };
+        let summary = Some(post_description(&p)).filter(|d| !d.is_empty());
+
+        JsonFeedItem {
+            id: post_url.clone(),
+            url: post_url,
+            title: p.title.clone(),
+            content_html: render_post(&p).html.clone(),
+            summary,
+            image: p.image.clone(),
+            date_published: p.date.to_rfc3339(),
+            date_modified: p.last_updated.to_rfc3339(),
+            authors: vec![author],
+            tags: p.tags.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::blog_service::{deserialize_some, find_blog, update_blog_rss};
+use crate::entity::blog_metadata::{
+    ActiveModel as BlogMetaActive, Column as BlogMetaColumn, Entity as BlogMetaEntity,
+    Model as BlogMeta,
+};
+use crate::entity::blog_posts::{Column as BlogPostColumn, Entity as BlogPostEntity};
+use crate::{
+    server::{full, require_scope, ApiScope},
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our blog metadata write handler function.
+#[derive(Deserialize)]
+struct NewBlogRequest {
+    title: String,
+    slug: String,
+    blog_url: String,
+    syndication_url: String,
+    author: String,
+    author_email: Option<String>,
+    author_url: Option<String>,
+}
+
+/// Utility struct for our blog metadata edit handler function. `author_email` and `author_url`
+/// can be cleared with an explicit `null`.
+#[derive(Deserialize)]
+struct BlogEditRequest {
+    title: Option<String>,
+    slug: Option<String>,
+    blog_url: Option<String>,
+    syndication_url: Option<String>,
+    author: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    author_email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    author_url: Option<Option<String>>,
+}
+
+/// Handler function for GET /blog that returns the blog's metadata, or the default blog's
+/// without one in the URL.
+pub(crate) async fn get_blog_metadata(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let blog_metadata = match find_blog_metadata(db, blog_slug).await {
+        Ok(m) => m,
+        Err(r) => return Ok(r),
+    };
+    let json =
+        serde_json::to_string(&blog_metadata).expect("Error converting blog metadata to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /blog and POST /blogs that writes a new blog's metadata and its
+/// empty feeds. POST /blog sets up the first blog, so it's refused once there is one.
+pub(crate) async fn write_blog_metadata(
+    ctx: &Context,
+    req: Request<Incoming>,
+    first_blog: bool,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let new_blog: NewBlogRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    if let Err(e) = validate_blog(
+        &new_blog.slug,
+        &new_blog.blog_url,
+        &new_blog.syndication_url,
+    ) {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(e))
+            .unwrap());
+    }
+    if first_blog {
+        match BlogMetaEntity::find().count(&*ctx.db).await {
+            Ok(0) => {}
+            Ok(_) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::CONFLICT)
+                    .body(full(
+                        b"Error: Blog metadata already exists, use PUT /api/blog".as_slice(),
+                    ))
+                    .unwrap());
+            }
+            Err(e) => {
+                error!("{}", e);
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap());
+            }
+        }
+    }
+    if let Some(r) = check_duplicate_slug(&ctx.db, &new_blog.slug).await {
+        return Ok(r);
+    }
+    let blog_active = BlogMetaActive {
+        id: NotSet,
+        title: Set(new_blog.title),
+        slug: Set(new_blog.slug),
+        blog_url: Set(new_blog.blog_url),
+        syndication_url: Set(new_blog.syndication_url),
+        last_updated: Set(FixedOffset::east_opt(0)
+            .unwrap()
+            .from_utc_datetime(&Utc::now().naive_utc())),
+        author: Set(new_blog.author),
+        author_email: Set(new_blog.author_email),
+        author_url: Set(new_blog.author_url),
+    };
+    let blog_returned = match blog_active.insert(&*ctx.db).await {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Some(r) = update_blog_rss(ctx, blog_returned.id).await {
+        return Ok(r);
+    };
+    let json =
+        serde_json::to_string(&blog_returned).expect("Error converting blog metadata to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for PUT /blog that edits the blog's metadata, or the default blog's without
+/// one in the URL. A new title is copied to the blog's posts. Everything but `last_updated`
+/// shows up in at least one of the blog's feeds, so they're regenerated whenever something
+/// changes.
+pub(crate) async fn edit_blog_metadata(
+    ctx: &Context,
+    req: Request<Incoming>,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::Admin).await {
+        return Ok(r);
+    }
+    let blog_metadata = match find_blog_metadata(&ctx.db, blog_slug).await {
+        Ok(m) => m,
+        Err(r) => return Ok(r),
+    };
+    let whole_body = req.collect().await?.aggregate();
+    let edits: BlogEditRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(e) => e,
+        Err(e) => {
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    let mut edited = blog_metadata.clone();
+    if let Some(title) = edits.title {
+        edited.title = title;
+    }
+    if let Some(slug) = edits.slug {
+        edited.slug = slug;
+    }
+    if let Some(blog_url) = edits.blog_url {
+        edited.blog_url = blog_url;
+    }
+    if let Some(syndication_url) = edits.syndication_url {
+        edited.syndication_url = syndication_url;
+    }
+    if let Some(author) = edits.author {
+        edited.author = author;
+    }
+    if let Some(author_email) = edits.author_email {
+        edited.author_email = author_email;
+    }
+    if let Some(author_url) = edits.author_url {
+        edited.author_url = author_url;
+    }
+    if edited == blog_metadata {
+        let json =
+            serde_json::to_string(&blog_metadata).expect("Error converting blog metadata to JSON");
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(full(json))
+            .unwrap());
+    }
+    if let Err(e) = validate_blog(&edited.slug, &edited.blog_url, &edited.syndication_url) {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(e))
+            .unwrap());
+    }
+    if edited.slug != blog_metadata.slug
+        && let Some(r) = check_duplicate_slug(&ctx.db, &edited.slug).await
+    {
+        return Ok(r);
+    }
+    let title_changed = edited.title != blog_metadata.title;
+    let blog_active = BlogMetaActive {
+        id: Set(edited.id),
+        title: Set(edited.title),
+        slug: Set(edited.slug),
+        blog_url: Set(edited.blog_url),
+        syndication_url: Set(edited.syndication_url),
+        last_updated: Set(FixedOffset::east_opt(0)
+            .unwrap()
+            .from_utc_datetime(&Utc::now().naive_utc())),
+        author: Set(edited.author),
+        author_email: Set(edited.author_email),
+        author_url: Set(edited.author_url),
+    };
+    let txn = ctx.db.begin().await?;
+    let blog_returned = match blog_active.update(&txn).await {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if title_changed
+        && let Err(e) = BlogPostEntity::update_many()
+            .col_expr(BlogPostColumn::BlogTitle, Expr::value(&blog_returned.title))
+            .filter(BlogPostColumn::BlogId.eq(blog_returned.id))
+            .exec(&txn)
+            .await
+    {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    txn.commit().await?;
+    if let Some(r) = update_blog_rss(ctx, blog_returned.id).await {
+        return Ok(r);
+    };
+    let json =
+        serde_json::to_string(&blog_returned).expect("Error converting blog metadata to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Checks a blog's slug can be used in URLs and its URLs are absolute HTTP(S) URLs.
+fn validate_blog(slug: &str, blog_url: &str, syndication_url: &str) -> Result<(), String> {
+    if slug.is_empty()
+        || !slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(
+            "Bad request: Blog slug must be lowercase letters, digits and hyphens".to_string(),
+        );
+    }
+    for (name, url) in [("blog_url", blog_url), ("syndication_url", syndication_url)] {
+        if !is_http_url(url) {
+            return Err(format!(
+                "Bad request: {} must be an absolute http or https URL",
+                name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the URL has an http or https scheme, a host and no whitespace.
+fn is_http_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+
+    !host.is_empty() && !url.chars().any(char::is_whitespace)
+}
+
+async fn check_duplicate_slug(db: &DatabaseConnection, slug: &str) -> Option<Response<BoxBody>> {
+    match BlogMetaEntity::find()
+        .filter(BlogMetaColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(None) => None,
+        Ok(Some(_)) => Some(
+            Response::builder()
+                .status(StatusCode::CONFLICT)
+                .body(full(b"Error: Duplicate blog slug".as_slice()))
+                .unwrap(),
+        ),
+        Err(e) => {
+            error!("{}", e);
+            Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap(),
+            )
+        }
+    }
+}
+
+/// Finds a blog's metadata by its slug, or the default blog's without one.
+async fn find_blog_metadata(
+    db: &DatabaseConnection,
+    blog_slug: Option<&str>,
+) -> Result<BlogMeta, Response<BoxBody>> {
+    if let Some(slug) = blog_slug {
+        return find_blog(db, slug).await;
+    }
+    match BlogMetaEntity::find()
+        .order_by_asc(BlogMetaColumn::Id)
+        .one(db)
+        .await
+    {
+        Ok(Some(m)) => Ok(m),
+        Ok(None) => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(
+                b"Not Found: No blog metadata, create it with POST /api/blog".as_slice(),
+            ))
+            .unwrap()),
+        Err(e) => {
+            error!("{}", e);
+            Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap())
+        }
+    }
+}
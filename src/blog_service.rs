@@ -1,4 +1,7 @@
-use chrono::{FixedOffset, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
 use http_body_util::BodyExt;
 use hyper::{
     body::{Buf, Incoming},
@@ -6,13 +9,23 @@ use hyper::{
 };
 use log::error;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    sea_query::{extension::postgres::PgExpr, Expr},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, NotSet,
+    Order, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::blog_atom::generate_atom_feed;
+use crate::api_key_service::{get_api_keys, revoke_api_key, write_api_key};
+use crate::author_service::{get_authors, handle_author_request, resolve_author, write_author};
+use crate::blog_atom::{
+    generate_atom_feed, generate_description, generate_rss_feed, post_url, BlogFeeds, CachedFeed,
+    TocEntry,
+};
+use crate::blog_json_feed::generate_json_feed;
+use crate::blog_metadata_service::{edit_blog_metadata, get_blog_metadata, write_blog_metadata};
 use crate::entity::blog_metadata::{
     ActiveModel as BlogMetaActive, Column as BlogMetaColumn, Entity as BlogMetaEntity,
+    Model as BlogMeta,
 };
 use crate::entity::blog_posts::{
     ActiveModel as BlogPostActive, Column as BlogPostColumn, Entity as BlogPostEntity,
@@ -21,19 +34,37 @@ use crate::entity::blog_posts::{
 use crate::entity::rss_feeds::{
     ActiveModel as RssFeedActive, Column as RssFeedColumn, Entity as RssFeedEntity,
 };
-use crate::entity::sea_orm_active_enums::ContentType;
+use crate::entity::sea_orm_active_enums::{ContentType, FeedFormat, PostStatus};
+use crate::highlight::highlight_css;
+use crate::page_service::{get_pages, handle_page_request};
+use crate::podcast_service::{
+    delete_podcast_episode, edit_podcast_episode, get_podcast_episode, get_podcast_episodes,
+    get_podcast_rss, write_podcast_episode,
+};
+use crate::related_service::{clear_related_cache, get_related_posts};
+use crate::revision_service::{handle_revision_request, record_revision};
+use crate::search_service::search_blog_posts;
+use crate::series_service::{
+    get_series_list, handle_series_request, resolve_series_position, series_info, write_series,
+    SeriesInfo,
+};
+use crate::tag_service::{get_tags, handle_tag_request};
 use crate::{
-    server::{api_key_auth, full},
-    BoxBody, BoxResult, Context, BASE_URL,
+    server::{
+        api_key_auth, full, http_date, is_not_modified, not_modified, require_scope, sha256_string,
+        ApiScope,
+    },
+    BoxBody, BoxResult, Context,
 };
 
 /// Utility struct for our GET /posts/ handler that returns a sorted collection of all blog posts.
 #[derive(Deserialize, Serialize)]
-struct BlogPostInfo {
+pub(crate) struct BlogPostInfo {
     title: String,
     slug: String,
     date: String,
     visible: bool,
+    status: PostStatus,
 }
 
 impl From<BlogPost> for BlogPostInfo {
@@ -43,6 +74,74 @@ impl From<BlogPost> for BlogPostInfo {
             slug: v.slug,
             date: v.date.to_rfc3339(),
             visible: v.visible,
+            status: v.status,
+        }
+    }
+}
+
+/// `Cache-Control` policies. Feed readers can reuse the feed for a few minutes, posts for a
+/// minute, and anything only authenticated callers can see mustn't be stored by shared caches.
+const FEED_CACHE_CONTROL: &str = "public, max-age=300";
+pub(crate) const POST_CACHE_CONTROL: &str = "public, max-age=60";
+pub(crate) const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// Page size for GET /posts when the caller doesn't ask for one, and the most we'll return.
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+
+/// Query parameters accepted by our GET /posts handler. Serialized again to build the
+/// pagination links, so filters carry over from page to page.
+#[derive(Clone, Deserialize, Serialize)]
+struct PostsQuery {
+    page: Option<u64>,
+    per_page: Option<u64>,
+    tag: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    visible: Option<bool>,
+    sort: Option<PostSort>,
+}
+
+/// Orderings for GET /posts. Ties are broken by newest database id.
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PostSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    TitleAsc,
+    TitleDesc,
+    UpdatedDesc,
+    UpdatedAsc,
+}
+
+/// Utility struct for our GET /posts/[slug] handler that returns a post with its text rendered
+/// to HTML, the table of contents of its headings, its length, its neighboring posts and its
+/// place in its series.
+#[derive(Serialize)]
+struct BlogPostResponse<'a> {
+    #[serde(flatten)]
+    post: BlogPost,
+    toc: &'a [TocEntry],
+    word_count: usize,
+    reading_time: usize,
+    previous_post: Option<PostLink>,
+    next_post: Option<PostLink>,
+    series: Option<SeriesInfo>,
+}
+
+/// A neighboring post linked from GET /posts/[slug].
+#[derive(Serialize)]
+pub(crate) struct PostLink {
+    title: String,
+    slug: String,
+}
+
+impl From<&BlogPost> for PostLink {
+    fn from(v: &BlogPost) -> Self {
+        Self {
+            title: v.title.clone(),
+            slug: v.slug.clone(),
         }
     }
 }
@@ -52,23 +151,87 @@ impl From<BlogPost> for BlogPostInfo {
 struct EditRequest {
     title: Option<String>,
     text: Option<String>,
+    description: Option<String>,
     tags: Option<Vec<String>>,
     visible: Option<bool>,
+    status: Option<PostStatus>,
+    publish_at: Option<DateTime<FixedOffset>>,
+    /// `null` takes the post out of its series.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    series_id: Option<Option<i32>>,
+    series_position: Option<i32>,
+    author_id: Option<i32>,
+}
+
+/// Deserializes a field that's present as `Some`, so an explicit `null` can be told apart from
+/// a missing field.
+pub(crate) fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 /// Main routing function.
 pub async fn handle_request(req: Request<Incoming>, ctx: Context) -> BoxResult<Response<BoxBody>> {
     match (req.method(), req.uri().path()) {
-        (&Method::GET, "/api/posts") => get_blog_posts(&ctx.db).await,
+        (_, path)
+            if path.starts_with("/api/posts/") && path.split("/").nth(4) == Some("revisions") =>
+        {
+            handle_revision_request(&ctx, req).await
+        }
+        (&Method::GET, path)
+            if path.starts_with("/api/posts/") && path.split("/").nth(4) == Some("related") =>
+        {
+            get_related_posts(&ctx.db, &req).await
+        }
+        (&Method::GET, "/api/posts") => get_blog_posts(&ctx.db, &req, None).await,
         (&Method::GET, path) if path.starts_with("/api/posts/") => {
             get_blog_post(&ctx.db, &req).await
         }
-        (&Method::POST, "/api/posts") => write_blog_post(&ctx, req).await,
+        (&Method::POST, "/api/posts") => write_blog_post(&ctx, req, None).await,
         (&Method::PUT, path) if path.starts_with("/api/posts/") => edit_blog_post(&ctx, req).await,
         (&Method::DELETE, path) if path.starts_with("/api/posts/") => {
             delete_blog_post(&ctx, req).await
         }
-        (&Method::GET, "/api/atom") => get_blog_rss(&ctx).await,
+        (&Method::GET, "/api/atom") => get_blog_rss(&ctx, &req, None).await,
+        (&Method::GET, "/api/rss") => get_blog_rss_channel(&ctx, None).await,
+        (&Method::GET, "/api/feed.json") => get_blog_json_feed(&ctx, None).await,
+        (&Method::GET, "/api/blog") => get_blog_metadata(&ctx.db, &req, None).await,
+        (&Method::POST, "/api/blog") => write_blog_metadata(&ctx, req, true).await,
+        (&Method::PUT, "/api/blog") => edit_blog_metadata(&ctx, req, None).await,
+        (&Method::POST, "/api/blogs") => write_blog_metadata(&ctx, req, false).await,
+        (_, path) if path.starts_with("/api/blogs/") => handle_blog_request(&ctx, req).await,
+        (&Method::GET, "/api/highlight.css") => get_highlight_css().await,
+        (&Method::GET, "/api/search") => search_blog_posts(&ctx.db, &req).await,
+        (&Method::GET, "/api/tags") => get_tags(&ctx.db).await,
+        (&Method::GET, "/api/keys") => get_api_keys(&ctx.db, &req).await,
+        (&Method::POST, "/api/keys") => write_api_key(&ctx.db, req).await,
+        (&Method::DELETE, path) if path.starts_with("/api/keys/") => {
+            revoke_api_key(&ctx.db, &req).await
+        }
+        (&Method::GET, "/api/authors") => get_authors(&ctx.db, &req).await,
+        (&Method::POST, "/api/authors") => write_author(&ctx.db, req).await,
+        (_, path) if path.starts_with("/api/authors/") => handle_author_request(&ctx, req).await,
+        (&Method::GET, "/api/series") => get_series_list(&ctx.db).await,
+        (&Method::POST, "/api/series") => write_series(&ctx, req).await,
+        (_, path) if path.starts_with("/api/series/") => handle_series_request(&ctx, req).await,
+        (_, path) if path.starts_with("/api/tags/") => handle_tag_request(&ctx, req).await,
+        (&Method::GET, "/api/pages") => get_pages(&ctx.db, &req).await,
+        (_, path) if path.starts_with("/api/pages/") => handle_page_request(&ctx, req).await,
+        (&Method::GET, "/api/podcasts") => get_podcast_episodes(&ctx.db, &req).await,
+        (&Method::GET, "/api/podcasts/rss") => get_podcast_rss(&ctx).await,
+        (&Method::GET, path) if path.starts_with("/api/podcasts/") => {
+            get_podcast_episode(&ctx.db, &req).await
+        }
+        (&Method::POST, "/api/podcasts") => write_podcast_episode(&ctx, req).await,
+        (&Method::PUT, path) if path.starts_with("/api/podcasts/") => {
+            edit_podcast_episode(&ctx, req).await
+        }
+        (&Method::DELETE, path) if path.starts_with("/api/podcasts/") => {
+            delete_podcast_episode(&ctx, req).await
+        }
         _ => Ok(Response::builder()
             .status(StatusCode::NOT_FOUND)
             .header("Content-Type", "text/plain")
@@ -77,23 +240,129 @@ pub async fn handle_request(req: Request<Incoming>, ctx: Context) -> BoxResult<R
     }
 }
 
-/// Handler function for GET /posts that returns a sorted collection of all blog posts.
-async fn get_blog_posts(db: &DatabaseConnection) -> BoxResult<Response<BoxBody>> {
-    let posts_vec = match BlogPostEntity::find()
-        .order_by_desc(BlogPostColumn::Date)
-        .all(db)
-        .await
-    {
+/// Routing function for everything under /api/blogs/[blog].
+async fn handle_blog_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let blog_slug = path_vec[3];
+    match (req.method(), &path_vec[4..]) {
+        (&Method::GET, []) => get_blog_metadata(&ctx.db, &req, Some(blog_slug)).await,
+        (&Method::PUT, []) => edit_blog_metadata(ctx, req, Some(blog_slug)).await,
+        (&Method::GET, ["posts"]) => {
+            let blog_metadata = match find_blog(&ctx.db, blog_slug).await {
+                Ok(m) => m,
+                Err(r) => return Ok(r),
+            };
+            get_blog_posts(&ctx.db, &req, Some(blog_metadata.id)).await
+        }
+        (&Method::POST, ["posts"]) => write_blog_post(ctx, req, Some(blog_slug)).await,
+        (&Method::GET, ["atom"]) => get_blog_rss(ctx, &req, Some(blog_slug)).await,
+        (&Method::GET, ["rss"]) => get_blog_rss_channel(ctx, Some(blog_slug)).await,
+        (&Method::GET, ["feed.json"]) => get_blog_json_feed(ctx, Some(blog_slug)).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /posts that returns a sorted, paginated collection of blog posts,
+/// optionally only the given blog's. Filtering by tag, date range and visibility, sorting and
+/// pagination all happen in the database query. The total count goes in `X-Total-Count` and
+/// links to neighboring pages in `Link`. Drafts and scheduled posts are only included for
+/// authenticated callers. Supports conditional requests, with validators derived from the
+/// page's posts' `last_updated`.
+async fn get_blog_posts(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    blog_id: Option<i32>,
+) -> BoxResult<Response<BoxBody>> {
+    let params: PostsQuery = match serde_urlencoded::from_str(req.uri().query().unwrap_or("")) {
         Ok(p) => p,
         Err(e) => {
-            error!("{}", e);
+            let err_string = format!("Bad request: Invalid query parameters: {}", e);
             return Ok(Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .status(StatusCode::BAD_REQUEST)
                 .header("Content-Type", "text/plain")
-                .body(full(b"Database error".as_slice()))
+                .body(full(err_string))
                 .unwrap());
         }
     };
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let mut query = BlogPostEntity::find();
+    if let Some(blog_id) = blog_id {
+        query = query.filter(BlogPostColumn::BlogId.eq(blog_id));
+    }
+    // Callers without an API key only ever see visible posts, whatever they ask for.
+    if !authenticated {
+        query = query.filter(BlogPostColumn::Visible.eq(true));
+    } else if let Some(visible) = params.visible {
+        query = query.filter(BlogPostColumn::Visible.eq(visible));
+    }
+    if let Some(tag) = &params.tag {
+        query = query.filter(Expr::col(BlogPostColumn::Tags).contains(vec![tag.clone()]));
+    }
+    for (bound, is_end) in [(&params.from, false), (&params.to, true)] {
+        let Some(bound) = bound else {
+            continue;
+        };
+        let Some(date) = parse_date_filter(bound, is_end) else {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("Content-Type", "text/plain")
+                .body(full(
+                    b"Bad request: Dates should be RFC 3339 or YYYY-MM-DD".as_slice(),
+                ))
+                .unwrap());
+        };
+        query = if is_end {
+            query.filter(BlogPostColumn::Date.lte(date))
+        } else {
+            query.filter(BlogPostColumn::Date.gte(date))
+        };
+    }
+    let (sort_column, sort_order) = match params.sort.unwrap_or_default() {
+        PostSort::DateDesc => (BlogPostColumn::Date, Order::Desc),
+        PostSort::DateAsc => (BlogPostColumn::Date, Order::Asc),
+        PostSort::TitleAsc => (BlogPostColumn::Title, Order::Asc),
+        PostSort::TitleDesc => (BlogPostColumn::Title, Order::Desc),
+        PostSort::UpdatedDesc => (BlogPostColumn::LastUpdated, Order::Desc),
+        PostSort::UpdatedAsc => (BlogPostColumn::LastUpdated, Order::Asc),
+    };
+    let paginator = query
+        .order_by(sort_column, sort_order)
+        .order_by_desc(BlogPostColumn::Id)
+        .paginate(db, per_page);
+    let (total, posts_vec) =
+        match tokio::try_join!(paginator.num_items(), paginator.fetch_page(page - 1)) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("{}", e);
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap());
+            }
+        };
+    let (etag, last_modified) = posts_validators(&posts_vec, total);
+    let cache_control = if authenticated {
+        PRIVATE_CACHE_CONTROL
+    } else {
+        POST_CACHE_CONTROL
+    };
+    if is_not_modified(req, &etag, &last_modified) {
+        return Ok(not_modified(&etag, &last_modified, cache_control));
+    }
     let posts_info: Vec<BlogPostInfo> = posts_vec.into_iter().map(|p| p.into()).collect();
     let json =
         serde_json::to_string(&posts_info).expect("Error converting blog post info vec to JSON");
@@ -101,16 +370,97 @@ async fn get_blog_posts(db: &DatabaseConnection) -> BoxResult<Response<BoxBody>>
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "application/json")
+        .header("ETag", etag)
+        .header("Last-Modified", http_date(&last_modified))
+        .header("Cache-Control", cache_control)
+        .header("Vary", "Authorization")
+        .header("X-Total-Count", total)
+        .header(
+            "Link",
+            pagination_links(req.uri().path(), &params, page, per_page, total),
+        )
         .body(full(json))
         .unwrap())
 }
 
+/// Builds the ETag and `Last-Modified` time for a page of GET /posts results. The ETag hashes
+/// each post's id and `last_updated` along with the total count, so it changes when any post
+/// on the page changes or posts move between pages.
+fn posts_validators(posts: &[BlogPost], total: u64) -> (String, DateTime<FixedOffset>) {
+    // SHA-256 rather than std's hasher, whose output can change between Rust releases and
+    // would invalidate every client's cache on upgrade.
+    let mut versions = total.to_string();
+    for post in posts {
+        versions.push_str(&format!(
+            ",{}:{}",
+            post.id,
+            post.last_updated.timestamp_micros()
+        ));
+    }
+    let etag = format!("\"{}\"", sha256_string(versions.as_bytes()));
+    let last_modified = posts
+        .iter()
+        .map(|p| p.last_updated)
+        .max()
+        .unwrap_or_else(|| DateTime::UNIX_EPOCH.fixed_offset());
+
+    (etag, last_modified)
+}
+
+/// Parses a GET /posts date filter, either an RFC 3339 timestamp or a plain `YYYY-MM-DD` date.
+/// Plain dates cover the whole day, so they're taken as its start, or its end when `is_end`.
+fn parse_date_filter(s: &str, is_end: bool) -> Option<DateTime<FixedOffset>> {
+    if let Ok(d) = DateTime::parse_from_rfc3339(s) {
+        return Some(d);
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let naive = if is_end {
+        date.and_hms_micro_opt(23, 59, 59, 999_999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+
+    Some(FixedOffset::east_opt(0).unwrap().from_utc_datetime(&naive))
+}
+
+/// Builds the `Link` header for a page of GET /posts results, keeping the caller's filters.
+fn pagination_links(
+    path: &str,
+    params: &PostsQuery,
+    page: u64,
+    per_page: u64,
+    total: u64,
+) -> String {
+    let last_page = total.div_ceil(per_page).max(1);
+    let mut links = vec![(1, "first")];
+    if page > 1 {
+        links.push((page.min(last_page + 1) - 1, "prev"));
+    }
+    if page < last_page {
+        links.push((page + 1, "next"));
+    }
+    links.push((last_page, "last"));
+
+    links
+        .into_iter()
+        .map(|(p, rel)| {
+            let link_params = PostsQuery {
+                page: Some(p),
+                per_page: Some(per_page),
+                ..params.clone()
+            };
+            let query_string = serde_urlencoded::to_string(&link_params).unwrap_or_default();
+            format!("<{}?{}>; rel=\"{}\"", path, query_string, rel)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 async fn get_blog_post(
     db: &DatabaseConnection,
     req: &Request<Incoming>,
 ) -> BoxResult<Response<BoxBody>> {
-    use crate::blog_atom::get_markdown_options;
-    use pulldown_cmark::{html::push_html, Parser};
+    use crate::blog_atom::render_post;
 
     let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
     let slug = path_vec[3];
@@ -138,8 +488,9 @@ async fn get_blog_post(
                 .unwrap());
         }
     };
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
     let mut post = match maybe_post {
-        Some(p) if p.visible => p,
+        Some(p) if p.visible || authenticated => p,
         _ => {
             return Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
@@ -147,33 +498,114 @@ async fn get_blog_post(
                 .unwrap())
         }
     };
-    let md_options = get_markdown_options();
-    let mut parsed_html = String::with_capacity(2048);
-    let parser = Parser::new_ext(&post.text, md_options);
-    push_html(&mut parsed_html, parser);
-    post.text = parsed_html;
-    let json = serde_json::to_string(&post).expect("Error converting blog post to JSON");
+    let neighbor_slugs: Vec<String> = [&post.previous, &post.next]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let neighbors = if neighbor_slugs.is_empty() {
+        Vec::new()
+    } else {
+        match BlogPostEntity::find()
+            .filter(BlogPostColumn::Slug.is_in(neighbor_slugs))
+            .all(db)
+            .await
+        {
+            Ok(n) => n,
+            Err(e) => {
+                error!("{}", e);
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap());
+            }
+        }
+    };
+    let post_link = |slug: &Option<String>| {
+        let slug = slug.as_ref()?;
+        neighbors
+            .iter()
+            .find(|p| &p.slug == slug)
+            .map(PostLink::from)
+    };
+    let previous_post = post_link(&post.previous);
+    let next_post = post_link(&post.next);
+    let series = match series_info(db, &post, authenticated).await {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    // Neighbors and series parts can change without this post being updated, so they're part
+    // of the ETag.
+    let links_json = serde_json::to_string(&(&previous_post, &next_post, &series))
+        .expect("Error converting post links to JSON");
+    let links_hash = sha256_string(links_json.as_bytes());
+    let etag = format!(
+        "\"{}-{:x}-{}\"",
+        post.id,
+        post.last_updated.timestamp_micros(),
+        links_hash
+    );
+    let last_modified = post.last_updated;
+    // Authenticated callers can see drafts and scheduled posts linked from the post, so only
+    // what anyone could see goes in shared caches.
+    let cache_control = if post.visible && !authenticated {
+        POST_CACHE_CONTROL
+    } else {
+        PRIVATE_CACHE_CONTROL
+    };
+    if is_not_modified(req, &etag, &last_modified) {
+        return Ok(not_modified(&etag, &last_modified, cache_control));
+    }
+    let rendered = render_post(&post);
+    post.text = rendered.html.clone();
+    if post.description.trim().is_empty() {
+        post.description = rendered.excerpt.clone();
+    }
+    let post_response = BlogPostResponse {
+        post,
+        toc: &rendered.toc,
+        word_count: rendered.word_count,
+        reading_time: rendered.reading_time,
+        previous_post,
+        next_post,
+        series,
+    };
+    let json = serde_json::to_string(&post_response).expect("Error converting blog post to JSON");
 
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "application/json")
+        .header("ETag", etag)
+        .header("Last-Modified", http_date(&last_modified))
+        .header("Cache-Control", cache_control)
+        .header("Vary", "Authorization")
         .body(full(json))
         .unwrap())
 }
 
 /// Handler function for writing blog posts into the database. Authenticates, Parses request
 /// JSON, checks if we're adding a duplicate (returns error if so,) writes new post data to
-/// database, and updates Atom syndication XML.
-async fn write_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Response<BoxBody>> {
-    if !api_key_auth(&req) {
-        return Ok(Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("WWW-Authenticate", "ApiKey")
-            .body(full(b"Unauthorized: Requires API key".as_slice()))
-            .unwrap());
+/// database along with its first revision, relinks neighboring posts, and updates Atom
+/// syndication XML. The post goes in the blog named in the URL, or else the one with its
+/// `blog_id`, or else the one titled its `blog_title`.
+async fn write_blog_post(
+    ctx: &Context,
+    req: Request<Incoming>,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
     }
     let whole_body = req.collect().await?.aggregate();
-    let blog_post: BlogPost = match serde_json::from_reader(whole_body.reader()) {
+    let mut blog_post: BlogPost = match serde_json::from_reader(whole_body.reader()) {
         Ok(b) => b,
         Err(e) => {
             error!("{}", e);
@@ -184,7 +616,68 @@ async fn write_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Res
                 .unwrap());
         }
     };
-
+    let blog_query = match blog_slug {
+        Some(slug) => BlogMetaEntity::find().filter(BlogMetaColumn::Slug.eq(slug)),
+        None if blog_post.blog_id != 0 => BlogMetaEntity::find_by_id(blog_post.blog_id),
+        None => BlogMetaEntity::find().filter(BlogMetaColumn::Title.eq(&blog_post.blog_title)),
+    };
+    let blog_metadata = match blog_query.one(&*ctx.db).await {
+        Ok(Some(m)) => m,
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(b"Bad request: Blog not found".as_slice()))
+                .unwrap());
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    blog_post.blog_id = blog_metadata.id;
+    blog_post.blog_title = blog_metadata.title;
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    blog_post.status = match resolve_status(blog_post.status, blog_post.publish_at, now) {
+        Ok(s) => s,
+        Err(e) => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(e))
+                .unwrap());
+        }
+    };
+    blog_post.visible = blog_post.status == PostStatus::Published;
+    if blog_post.description.trim().is_empty() {
+        blog_post.description = generate_description(&blog_post.text);
+    }
+    blog_post.series_position = match blog_post.series_id {
+        Some(series_id) => {
+            match resolve_series_position(&*ctx.db, series_id, blog_post.series_position).await {
+                Ok(Some(p)) => Some(p),
+                Ok(None) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(full(b"Bad request: Series not found".as_slice()))
+                        .unwrap());
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    return Ok(Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .header("Content-Type", "text/plain")
+                        .body(full(b"Database error".as_slice()))
+                        .unwrap());
+                }
+            }
+        }
+        None => None,
+    };
     let maybe_duplicate = match BlogPostEntity::find()
         .filter(BlogPostColumn::Slug.eq(&blog_post.slug))
         .one(&*ctx.db)
@@ -206,8 +699,42 @@ async fn write_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Res
             .body(full(b"Error: Duplicate slug/post title".as_slice()))
             .unwrap());
     }
-    let blog_post_active: BlogPostActive = blog_post.into();
-    let blog_post_returned = match blog_post_active.insert(&*ctx.db).await {
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let author = match resolve_author(&txn, blog_post.author_id, &blog_post.author).await {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(b"Bad request: Author not found".as_slice()))
+                .unwrap());
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    blog_post.author_id = Some(author.id);
+    blog_post.author = author.name;
+    let mut blog_post_active: BlogPostActive = blog_post.into();
+    // Let the database assign the id rather than inserting the deserialized default.
+    blog_post_active.id = NotSet;
+    // Trusting a post's raw HTML can only be turned on in the database, not with an API key.
+    blog_post_active.trust_raw_html = Set(false);
+    let blog_post_returned = match blog_post_active.insert(&txn).await {
         Ok(b) => b,
         Err(e) => {
             error!("{}", e);
@@ -218,13 +745,40 @@ async fn write_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Res
                 .unwrap());
         }
     };
-    if let Some(r) = update_blog_rss(ctx).await {
+    if let Err(e) = record_revision(&txn, None, &blog_post_returned).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = link_blog_posts(&txn).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if blog_post_returned.status == PostStatus::Scheduled {
+        ctx.scheduler.notify_one();
+    }
+    if let Some(r) = update_blog_rss(ctx, blog_post_returned.blog_id).await {
         return Ok(r);
     };
-    if let Some(r) = set_blog_updated(&ctx.db, &blog_post_returned.blog_title).await {
+    if let Some(r) = set_blog_updated(&ctx.db, blog_post_returned.blog_id).await {
         return Ok(r);
     };
-    let response_location = format!("{}{}", BASE_URL.get().unwrap(), &blog_post_returned.slug);
+    let response_location = post_url(&blog_metadata.blog_url, &blog_post_returned.slug);
 
     Ok(Response::builder()
         .status(StatusCode::CREATED)
@@ -234,12 +788,8 @@ async fn write_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Res
 }
 
 async fn edit_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Response<BoxBody>> {
-    if !api_key_auth(&req) {
-        return Ok(Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("WWW-Authenticate", "ApiKey")
-            .body(full(b"Unauthorized: Requires API key".as_slice()))
-            .unwrap());
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
     }
     let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
     let slug = path_vec[3].to_owned();
@@ -287,25 +837,126 @@ async fn edit_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Resp
                 .unwrap())
         }
     };
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    // An explicit status wins over the older visible flag, which maps onto published/draft.
+    let requested_status = edits.status.or(edits.visible.map(|v| {
+        if v {
+            PostStatus::Published
+        } else {
+            PostStatus::Draft
+        }
+    }));
+    let new_status = if requested_status.is_some() || edits.publish_at.is_some() {
+        let status = requested_status.unwrap_or(blog_post.status);
+        let publish_at = edits.publish_at.or(blog_post.publish_at);
+        match resolve_status(status, publish_at, now) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full(e))
+                    .unwrap());
+            }
+        }
+    } else {
+        None
+    };
+    // Moving a post to another series without a position adds it to the end of that series.
+    let series_id = edits.series_id.unwrap_or(blog_post.series_id);
+    let series_position = match series_id {
+        Some(id) if edits.series_id.is_some() || edits.series_position.is_some() => {
+            let position = match edits.series_position {
+                None if blog_post.series_id == series_id => blog_post.series_position,
+                p => p,
+            };
+            match resolve_series_position(&*ctx.db, id, position).await {
+                Ok(Some(p)) => Some(p),
+                Ok(None) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(full(b"Bad request: Series not found".as_slice()))
+                        .unwrap());
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    return Ok(Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .header("Content-Type", "text/plain")
+                        .body(full(b"Database error".as_slice()))
+                        .unwrap());
+                }
+            }
+        }
+        Some(_) => blog_post.series_position,
+        None => None,
+    };
+    let author = match edits.author_id {
+        Some(id) => match resolve_author(&*ctx.db, Some(id), "").await {
+            Ok(Some(a)) => Some(a),
+            Ok(None) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full(b"Bad request: Author not found".as_slice()))
+                    .unwrap());
+            }
+            Err(e) => {
+                error!("{}", e);
+                return Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap());
+            }
+        },
+        None => None,
+    };
+    let previous = blog_post.clone();
     let mut blog_post_active: BlogPostActive = blog_post.into();
-    if edits.title.is_some() {
-        blog_post_active.title = Set(edits.title.unwrap().to_owned());
+    blog_post_active.series_id = Set(series_id);
+    blog_post_active.series_position = Set(series_position);
+    if let Some(a) = author {
+        blog_post_active.author_id = Set(Some(a.id));
+        blog_post_active.author = Set(a.name);
     }
-    if edits.text.is_some() {
-        blog_post_active.text = Set(edits.text.unwrap().to_owned());
+    if let Some(title) = edits.title {
+        blog_post_active.title = Set(title);
     }
+    let text = edits.text.unwrap_or_else(|| previous.text.clone());
+    // Generated descriptions are regenerated when the text changes, hand-written ones are kept.
+    let description = match edits.description {
+        Some(d) if !d.trim().is_empty() => d,
+        Some(_) => generate_description(&text),
+        None if has_generated_description(&previous) => generate_description(&text),
+        None => previous.description.clone(),
+    };
+    blog_post_active.text = Set(text);
+    blog_post_active.description = Set(description);
     if edits.tags.is_some() {
         blog_post_active.tags = Set(edits.tags.clone());
     }
-    if edits.visible.is_some() {
-        blog_post_active.visible = Set(edits.visible.unwrap());
+    if edits.publish_at.is_some() {
+        blog_post_active.publish_at = Set(edits.publish_at);
+    }
+    if let Some(status) = new_status {
+        blog_post_active.status = Set(status);
+        blog_post_active.visible = Set(status == PostStatus::Published);
     }
     blog_post_active.edited = Set(true);
-    let now = FixedOffset::east_opt(0)
-        .unwrap()
-        .from_utc_datetime(&Utc::now().naive_utc());
     blog_post_active.last_updated = Set(now);
-    let blog_post_returned = match blog_post_active.update(&*ctx.db).await {
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let blog_post_returned = match blog_post_active.update(&txn).await {
         Ok(b) => b,
         Err(e) => {
             error!("{}", e);
@@ -316,10 +967,48 @@ async fn edit_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Resp
                 .unwrap());
         }
     };
-    if let Some(r) = update_blog_rss(ctx).await {
+    // Only content edits get a revision, not status or scheduling changes.
+    let content_changed = (&previous.title, &previous.text, &previous.tags)
+        != (
+            &blog_post_returned.title,
+            &blog_post_returned.text,
+            &blog_post_returned.tags,
+        );
+    if content_changed
+        && let Err(e) = record_revision(&txn, Some(&previous), &blog_post_returned).await
+    {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if previous.visible != blog_post_returned.visible
+        && let Err(e) = link_blog_posts(&txn).await
+    {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if blog_post_returned.status == PostStatus::Scheduled {
+        ctx.scheduler.notify_one();
+    }
+    if let Some(r) = update_blog_rss(ctx, blog_post_returned.blog_id).await {
         return Ok(r);
     };
-    if let Some(r) = set_blog_updated(&ctx.db, &blog_post_returned.blog_title).await {
+    if let Some(r) = set_blog_updated(&ctx.db, blog_post_returned.blog_id).await {
         return Ok(r);
     };
     let success_string = format!("Post successfully edited: {}", &blog_post_returned.slug);
@@ -330,13 +1019,15 @@ async fn edit_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Resp
         .unwrap())
 }
 
+/// Whether the post's description was generated from its text rather than written by hand, so
+/// it should be regenerated when the text changes.
+pub(crate) fn has_generated_description(post: &BlogPost) -> bool {
+    post.description.trim().is_empty() || post.description == generate_description(&post.text)
+}
+
 async fn delete_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Response<BoxBody>> {
-    if !api_key_auth(&req) {
-        return Ok(Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("WWW-Authenticate", "ApiKey")
-            .body(full(b"Unauthorized: Requires API key".as_slice()))
-            .unwrap());
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::DeletePosts).await {
+        return Ok(r);
     }
     let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
     let slug = path_vec[3].to_owned();
@@ -349,21 +1040,49 @@ async fn delete_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Re
             ))
             .unwrap());
     }
-    let mut blog_post: BlogPostActive = match BlogPostEntity::find()
+    let deleted_post = match BlogPostEntity::find()
         .filter(BlogPostColumn::Slug.eq(&slug))
+        .filter(BlogPostColumn::Status.ne(PostStatus::Deleted))
         .one(&*ctx.db)
-        .await?
+        .await
     {
-        Some(p) => p.into(),
-        None => {
+        Ok(Some(p)) => p,
+        Ok(None) => {
             return Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
                 .body(full(b"Not Found".as_slice()))
                 .unwrap())
         }
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
     };
+    // The slug is freed for new posts, so the deleted post keeps a unique one of its own.
+    let freed_slug = format!("{}-deleted-{}", &slug, deleted_post.id);
+    let mut blog_post: BlogPostActive = deleted_post.into();
+    blog_post.slug = Set(freed_slug);
+    blog_post.status = Set(PostStatus::Deleted);
     blog_post.visible = Set(false);
-    let blog_post_returned = match blog_post.update(&*ctx.db).await {
+    blog_post.last_updated = Set(FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc()));
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let blog_post_returned = match blog_post.update(&txn).await {
         Ok(p) => p,
         Err(e) => {
             error!("{}", e);
@@ -374,10 +1093,26 @@ async fn delete_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Re
                 .unwrap());
         }
     };
-    if let Some(r) = update_blog_rss(ctx).await {
+    if let Err(e) = link_blog_posts(&txn).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = update_blog_rss(ctx, blog_post_returned.blog_id).await {
         return Ok(r);
     };
-    if let Some(r) = set_blog_updated(&ctx.db, &blog_post_returned.blog_title).await {
+    if let Some(r) = set_blog_updated(&ctx.db, blog_post_returned.blog_id).await {
         return Ok(r);
     };
     let success_string = format!("Post successfully deleted: {}", &slug);
@@ -388,26 +1123,110 @@ async fn delete_blog_post(ctx: &Context, req: Request<Incoming>) -> BoxResult<Re
         .unwrap())
 }
 
-async fn update_blog_rss(ctx: &Context) -> Option<Response<BoxBody>> {
-    let new_feed = match generate_atom_feed(&ctx.db).await {
-        Ok(f) => f,
-        Err(e) => {
-            error!("{}", e);
+/// Checks a requested post status against its publish time. Scheduled posts need a
+/// `publish_at`, and ones whose time has already passed are published straight away.
+fn resolve_status(
+    status: PostStatus,
+    publish_at: Option<DateTime<FixedOffset>>,
+    now: DateTime<FixedOffset>,
+) -> Result<PostStatus, &'static str> {
+    match (status, publish_at) {
+        (PostStatus::Scheduled, None) => Err("Bad request: Scheduled posts require publish_at"),
+        (PostStatus::Scheduled, Some(t)) if t <= now => Ok(PostStatus::Published),
+        (s, _) => Ok(s),
+    }
+}
+
+/// Publishes every scheduled post whose `publish_at` has passed, using that time as the post
+/// date, and relinks neighboring posts and regenerates the Atom feed if any went live. Returns
+/// when the next scheduled post is due, if there is one.
+pub(crate) async fn publish_scheduled_posts(
+    ctx: &Context,
+) -> BoxResult<Option<DateTime<FixedOffset>>> {
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    let due_posts = BlogPostEntity::find()
+        .filter(BlogPostColumn::Status.eq(PostStatus::Scheduled))
+        .filter(BlogPostColumn::PublishAt.lte(now))
+        .all(&*ctx.db)
+        .await?;
+    let mut blog_ids: Vec<i32> = Vec::new();
+    let txn = ctx.db.begin().await?;
+    for post in due_posts {
+        let publish_at = post.publish_at.unwrap_or(now);
+        if !blog_ids.contains(&post.blog_id) {
+            blog_ids.push(post.blog_id);
+        }
+        let mut blog_post_active: BlogPostActive = post.into();
+        blog_post_active.status = Set(PostStatus::Published);
+        blog_post_active.visible = Set(true);
+        blog_post_active.date = Set(publish_at);
+        blog_post_active.last_updated = Set(now);
+        blog_post_active.update(&txn).await?;
+    }
+    if !blog_ids.is_empty() {
+        link_blog_posts(&txn).await?;
+    }
+    txn.commit().await?;
+    for blog_id in blog_ids {
+        if update_blog_rss(ctx, blog_id).await.is_some() {
+            return Err(anyhow!("Error updating Atom feed for scheduled posts").into());
+        }
+        if set_blog_updated(&ctx.db, blog_id).await.is_some() {
+            return Err(anyhow!("Error setting blog updated for scheduled posts").into());
+        }
+    }
+    let next_due = BlogPostEntity::find()
+        .filter(BlogPostColumn::Status.eq(PostStatus::Scheduled))
+        .order_by_asc(BlogPostColumn::PublishAt)
+        .one(&*ctx.db)
+        .await?
+        .and_then(|p| p.publish_at);
+
+    Ok(next_due)
+}
+
+/// Links every visible post to the visible posts before and after it in its blog in date order
+/// through its `previous` and `next` slugs, and clears the links of every other post. Only rows
+/// whose links change are written.
+async fn link_blog_posts<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    db.execute_unprepared(
+        r#"UPDATE blog_posts SET previous = linked.previous, next = linked.next
+            FROM (
+                SELECT id,
+                    CASE WHEN visible THEN LAG(slug) OVER w END AS previous,
+                    CASE WHEN visible THEN LEAD(slug) OVER w END AS next
+                FROM blog_posts
+                WINDOW w AS (PARTITION BY blog_id, visible ORDER BY date, id)
+            ) AS linked
+            WHERE blog_posts.id = linked.id
+                AND (blog_posts.previous IS DISTINCT FROM linked.previous
+                    OR blog_posts.next IS DISTINCT FROM linked.next)"#,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Regenerates the blog's Atom, RSS and JSON feeds, stores the Atom and RSS ones in its
+/// `rss_feeds` rows, and replaces its feeds cached in `Context`.
+pub(crate) async fn update_blog_rss(ctx: &Context, blog_id: i32) -> Option<Response<BoxBody>> {
+    // Every post write ends up here, so it's where related posts are invalidated too.
+    clear_related_cache();
+    let blog_metadata = match BlogMetaEntity::find_by_id(blog_id).one(&*ctx.db).await {
+        Ok(Some(m)) => m,
+        Ok(None) => {
             return Some(
                 Response::builder()
                     .status(StatusCode::INTERNAL_SERVER_ERROR)
                     .header("Content-Type", "text/plain")
-                    .body(full(b"Error generating Atom feed".as_slice()))
+                    .body(full(
+                        b"Server error: Blog metadata not configured".as_slice(),
+                    ))
                     .unwrap(),
             );
         }
-    };
-    let atom_feed_model = match RssFeedEntity::find()
-        .filter(RssFeedColumn::ContentType.eq(ContentType::Blog))
-        .one(&*ctx.db)
-        .await
-    {
-        Ok(a) => a,
         Err(e) => {
             error!("{}", e);
             return Some(
@@ -419,14 +1238,15 @@ async fn update_blog_rss(ctx: &Context) -> Option<Response<BoxBody>> {
             );
         }
     };
-    let mut atom_feed_model: RssFeedActive = match atom_feed_model {
-        Some(a) => a.into(),
-        None => {
+    let new_feed = match generate_atom_feed(&ctx.db, &blog_metadata).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
             return Some(
                 Response::builder()
                     .status(StatusCode::INTERNAL_SERVER_ERROR)
                     .header("Content-Type", "text/plain")
-                    .body(full(b"Blog Atom XML not found".as_slice()))
+                    .body(full(b"Error generating Atom feed".as_slice()))
                     .unwrap(),
             );
         }
@@ -434,50 +1254,232 @@ async fn update_blog_rss(ctx: &Context) -> Option<Response<BoxBody>> {
     let now = FixedOffset::east_opt(0)
         .unwrap()
         .from_utc_datetime(&Utc::now().naive_utc());
-    atom_feed_model.last_updated = Set(now);
-    atom_feed_model.rss_xml_string = Set(new_feed.to_string());
-    match atom_feed_model.update(&*ctx.db).await {
-        Ok(_) => {}
+    let new_atom_xml = new_feed.to_string();
+    if let Some(r) = store_blog_feed(&ctx.db, blog_id, FeedFormat::Atom, &new_atom_xml, now).await {
+        return Some(r);
+    }
+    let new_rss_feed = match generate_rss_feed(&ctx.db, &blog_metadata).await {
+        Ok(f) => f,
         Err(e) => {
             error!("{}", e);
             return Some(
                 Response::builder()
                     .status(StatusCode::INTERNAL_SERVER_ERROR)
                     .header("Content-Type", "text/plain")
-                    .body(full(b"Database error".as_slice()))
+                    .body(full(b"Error generating RSS feed".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let new_rss_xml = new_rss_feed.to_string();
+    if let Some(r) = store_blog_feed(&ctx.db, blog_id, FeedFormat::Rss, &new_rss_xml, now).await {
+        return Some(r);
+    }
+    let new_json_feed = match generate_json_feed(&ctx.db, &blog_metadata).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Error generating JSON feed".as_slice()))
                     .unwrap(),
             );
         }
     };
     {
-        let mut feed = ctx.atom_feed.write().unwrap();
-        *feed = new_feed;
+        let mut feeds = ctx.blog_feeds.write().unwrap();
+        feeds.insert(
+            blog_id,
+            BlogFeeds {
+                slug: blog_metadata.slug,
+                atom: CachedFeed {
+                    xml: new_atom_xml.into(),
+                    last_updated: now,
+                },
+                rss: new_rss_feed,
+                json: new_json_feed,
+            },
+        );
     }
 
     None
 }
 
-async fn get_blog_rss(ctx: &Context) -> BoxResult<Response<BoxBody>> {
-    let feed_string = {
-        ctx.atom_feed
+/// Stores a regenerated feed in the blog's `rss_feeds` row for its format. Rows are created on
+/// first use, e.g. for a newly added blog.
+async fn store_blog_feed(
+    db: &DatabaseConnection,
+    blog_id: i32,
+    feed_format: FeedFormat,
+    xml: &str,
+    now: DateTime<FixedOffset>,
+) -> Option<Response<BoxBody>> {
+    let feed_model = match RssFeedEntity::find()
+        .filter(RssFeedColumn::BlogId.eq(blog_id))
+        .filter(RssFeedColumn::FeedFormat.eq(feed_format))
+        .one(db)
+        .await
+    {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let result = match feed_model {
+        Some(f) => {
+            let mut feed_model: RssFeedActive = f.into();
+            feed_model.last_updated = Set(now);
+            feed_model.rss_xml_string = Set(xml.to_owned());
+            feed_model.update(db).await
+        }
+        None => {
+            let feed_model = RssFeedActive {
+                content_type: Set(Some(ContentType::Blog)),
+                feed_format: Set(feed_format),
+                blog_id: Set(Some(blog_id)),
+                rss_xml_string: Set(xml.to_owned()),
+                last_updated: Set(now),
+                ..Default::default()
+            };
+            feed_model.insert(db).await
+        }
+    };
+    if let Err(e) = result {
+        error!("{}", e);
+        return Some(
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap(),
+        );
+    };
+
+    None
+}
+
+/// Finds a blog's cached feeds by its slug, or the default blog's without one.
+fn find_blog_feeds<'a>(
+    feeds: &'a BTreeMap<i32, BlogFeeds>,
+    blog_slug: Option<&str>,
+) -> Option<&'a BlogFeeds> {
+    match blog_slug {
+        Some(slug) => feeds.values().find(|f| f.slug == slug),
+        None => feeds.values().next(),
+    }
+}
+
+/// Handler function for GET /atom that serves the cached Atom feed. Supports conditional
+/// requests, with validators derived from the feed's `last_updated` in `rss_feeds`.
+async fn get_blog_rss(
+    ctx: &Context,
+    req: &Request<Incoming>,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    let feed = {
+        let feeds = ctx
+            .blog_feeds
             .read()
-            .expect("Error reading Atom feed RwLock")
-            .to_string()
+            .expect("Error reading blog feeds RwLock");
+        find_blog_feeds(&feeds, blog_slug).map(|f| f.atom.clone())
+    };
+    let Some(feed) = feed else {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap());
     };
+    let etag = feed.etag();
+    if is_not_modified(req, &etag, &feed.last_updated) {
+        return Ok(not_modified(&etag, &feed.last_updated, FEED_CACHE_CONTROL));
+    }
 
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "application/atom+xml")
+        .header("ETag", etag)
+        .header("Last-Modified", http_date(&feed.last_updated))
+        .header("Cache-Control", FEED_CACHE_CONTROL)
+        .body(full(feed.xml))
+        .unwrap())
+}
+
+async fn get_blog_rss_channel(
+    ctx: &Context,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    let feed_string = {
+        let feeds = ctx
+            .blog_feeds
+            .read()
+            .expect("Error reading blog feeds RwLock");
+        find_blog_feeds(&feeds, blog_slug).map(|f| f.rss.to_string())
+    };
+    let Some(feed_string) = feed_string else {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap());
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/rss+xml")
         .body(full(feed_string))
         .unwrap())
 }
 
-async fn set_blog_updated(db: &DatabaseConnection, blog_title: &str) -> Option<Response<BoxBody>> {
-    let maybe_blog_meta = match BlogMetaEntity::find()
-        .filter(BlogMetaColumn::Title.eq(blog_title))
-        .one(db)
-        .await
-    {
+/// Handler function for GET /highlight.css that returns the stylesheet for the configured
+/// syntax highlighting theme.
+async fn get_highlight_css() -> BoxResult<Response<BoxBody>> {
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/css")
+        .header("Cache-Control", FEED_CACHE_CONTROL)
+        .body(full(highlight_css()))
+        .unwrap())
+}
+
+async fn get_blog_json_feed(
+    ctx: &Context,
+    blog_slug: Option<&str>,
+) -> BoxResult<Response<BoxBody>> {
+    let feed_string = {
+        let feeds = ctx
+            .blog_feeds
+            .read()
+            .expect("Error reading blog feeds RwLock");
+        find_blog_feeds(&feeds, blog_slug)
+            .map(|f| serde_json::to_string(&f.json).expect("Error converting JSON feed to JSON"))
+    };
+    let Some(feed_string) = feed_string else {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap());
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/feed+json")
+        .body(full(feed_string))
+        .unwrap())
+}
+
+pub(crate) async fn set_blog_updated(
+    db: &DatabaseConnection,
+    blog_id: i32,
+) -> Option<Response<BoxBody>> {
+    let maybe_blog_meta = match BlogMetaEntity::find_by_id(blog_id).one(db).await {
         Ok(m) => m,
         Err(e) => {
             error!("{}", e);
@@ -524,3 +1526,92 @@ async fn set_blog_updated(db: &DatabaseConnection, blog_title: &str) -> Option<R
 
     None
 }
+
+pub(crate) async fn find_blog(
+    db: &DatabaseConnection,
+    slug: &str,
+) -> Result<BlogMeta, Response<BoxBody>> {
+    match BlogMetaEntity::find()
+        .filter(BlogMetaColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(Some(m)) => Ok(m),
+        Ok(None) => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+        Err(e) => {
+            error!("{}", e);
+            Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query() -> PostsQuery {
+        PostsQuery {
+            page: None,
+            per_page: None,
+            tag: Some("rust".to_owned()),
+            from: None,
+            to: None,
+            visible: None,
+            sort: None,
+        }
+    }
+
+    #[test]
+    fn parses_date_filters() {
+        assert_eq!(
+            parse_date_filter("2026-10-16", false).unwrap().to_rfc3339(),
+            "2026-10-16T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_date_filter("2026-10-16", true).unwrap().to_rfc3339(),
+            "2026-10-16T23:59:59.999999+00:00"
+        );
+        assert_eq!(
+            parse_date_filter("2026-10-16T08:30:00+02:00", true)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-10-16T08:30:00+02:00"
+        );
+        assert!(parse_date_filter("16/10/2026", false).is_none());
+        assert!(parse_date_filter("2026-02-30", false).is_none());
+    }
+
+    #[test]
+    fn links_pages_keeping_filters() {
+        assert_eq!(
+            pagination_links("/api/posts", &query(), 2, 10, 35),
+            "</api/posts?page=1&per_page=10&tag=rust>; rel=\"first\", \
+             </api/posts?page=1&per_page=10&tag=rust>; rel=\"prev\", \
+             </api/posts?page=3&per_page=10&tag=rust>; rel=\"next\", \
+             </api/posts?page=4&per_page=10&tag=rust>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn links_single_page() {
+        assert_eq!(
+            pagination_links("/api/posts", &query(), 1, 10, 0),
+            "</api/posts?page=1&per_page=10&tag=rust>; rel=\"first\", \
+             </api/posts?page=1&per_page=10&tag=rust>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn links_past_the_last_page_back_to_it() {
+        let links = pagination_links("/api/posts", &query(), 9, 10, 20);
+        assert!(links.contains("page=2&per_page=10&tag=rust>; rel=\"prev\""));
+        assert!(!links.contains("rel=\"next\""));
+    }
+}
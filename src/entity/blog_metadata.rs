@@ -11,6 +11,8 @@ pub struct Model {
     pub id: i32,
     #[sea_orm(column_type = "Text")]
     pub title: String,
+    #[sea_orm(column_type = "Text", unique)]
+    pub slug: String,
     #[sea_orm(column_type = "Text")]
     pub blog_url: String,
     #[sea_orm(column_type = "Text")]
@@ -25,6 +27,23 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::blog_posts::Entity")]
+    BlogPosts,
+    #[sea_orm(has_many = "super::rss_feeds::Entity")]
+    RssFeeds,
+}
+
+impl Related<super::blog_posts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlogPosts.def()
+    }
+}
+
+impl Related<super::rss_feeds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RssFeeds.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
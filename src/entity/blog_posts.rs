@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
+use super::sea_orm_active_enums::PostStatus;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -14,12 +15,15 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub slug: String,
     #[sea_orm(column_type = "Text")]
+    #[serde(default)]
     pub blog_title: String,
     #[sea_orm(column_type = "Text")]
+    #[serde(default)]
     pub author: String,
     #[sea_orm(column_type = "Text")]
     pub text: String,
     #[sea_orm(column_type = "Text")]
+    #[serde(default)]
     pub description: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub image: Option<String>,
@@ -32,9 +36,73 @@ pub struct Model {
     pub last_updated: DateTimeWithTimeZone,
     pub visible: bool,
     pub edited: bool,
+    #[serde(default)]
+    pub status: PostStatus,
+    pub publish_at: Option<DateTimeWithTimeZone>,
+    #[serde(default)]
+    pub trust_raw_html: bool,
+    #[serde(default)]
+    pub series_id: Option<i32>,
+    #[serde(default)]
+    pub series_position: Option<i32>,
+    #[serde(default)]
+    pub blog_id: i32,
+    #[serde(default)]
+    pub author_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::authors::Entity",
+        from = "Column::AuthorId",
+        to = "super::authors::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Authors,
+    #[sea_orm(
+        belongs_to = "super::blog_metadata::Entity",
+        from = "Column::BlogId",
+        to = "super::blog_metadata::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    BlogMetadata,
+    #[sea_orm(has_many = "super::blog_post_revisions::Entity")]
+    BlogPostRevisions,
+    #[sea_orm(
+        belongs_to = "super::series::Entity",
+        from = "Column::SeriesId",
+        to = "super::series::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Series,
+}
+
+impl Related<super::authors::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Authors.def()
+    }
+}
+
+impl Related<super::blog_metadata::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlogMetadata.def()
+    }
+}
+
+impl Related<super::blog_post_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlogPostRevisions.def()
+    }
+}
+
+impl Related<super::series::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Series.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,6 +1,13 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
+pub mod api_keys;
+pub mod authors;
 pub mod blog_metadata;
+pub mod blog_post_revisions;
 pub mod blog_posts;
+pub mod pages;
+pub mod podcast_episodes;
+pub mod podcasts;
 pub mod rss_feeds;
 pub mod sea_orm_active_enums;
+pub mod series;
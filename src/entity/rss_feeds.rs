@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
 use super::sea_orm_active_enums::ContentType;
+use super::sea_orm_active_enums::FeedFormat;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -14,9 +15,26 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub rss_xml_string: String,
     pub last_updated: DateTimeWithTimeZone,
+    pub feed_format: FeedFormat,
+    pub blog_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::blog_metadata::Entity",
+        from = "Column::BlogId",
+        to = "super::blog_metadata::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    BlogMetadata,
+}
+
+impl Related<super::blog_metadata::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlogMetadata.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -11,3 +11,28 @@ pub enum ContentType {
     #[sea_orm(string_value = "Podcast")]
     Podcast,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "feed_format")]
+pub enum FeedFormat {
+    #[sea_orm(string_value = "Atom")]
+    Atom,
+    #[sea_orm(string_value = "Rss")]
+    Rss,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize, Default,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "post_status")]
+pub enum PostStatus {
+    #[sea_orm(string_value = "Draft")]
+    Draft,
+    #[sea_orm(string_value = "Scheduled")]
+    Scheduled,
+    #[default]
+    #[sea_orm(string_value = "Published")]
+    Published,
+    #[sea_orm(string_value = "Deleted")]
+    Deleted,
+}
@@ -0,0 +1,121 @@
+use std::sync::{LazyLock, OnceLock};
+
+use anyhow::anyhow;
+use log::warn;
+use pulldown_cmark::{html::push_html, CodeBlockKind, Event, Tag, TagEnd};
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+use crate::BoxResult;
+
+/// Theme used for highlighting CSS when `LS_HIGHLIGHT_THEME` isn't set.
+pub(crate) const DEFAULT_HIGHLIGHT_THEME: &str = "InspiredGitHub";
+/// Highlighted code is marked up with classes rather than inline styles so the site's CSS can
+/// theme it. They're prefixed so they can't collide with the site's own classes.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+/// Stylesheet for the configured theme, served at GET /api/highlight.css.
+static THEME_CSS: OnceLock<String> = OnceLock::new();
+
+/// Builds the stylesheet for one of syntect's bundled themes. Run once at startup.
+pub(crate) fn set_highlight_theme(name: &str) -> BoxResult<()> {
+    let theme_set = ThemeSet::load_defaults();
+    let Some(theme) = theme_set.themes.get(name) else {
+        let theme_names = theme_set
+            .themes
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(", ");
+        return Err(anyhow!(
+            "Unknown highlight theme \"{}\", expected one of: {}",
+            name,
+            theme_names
+        )
+        .into());
+    };
+    let css = css_for_theme_with_class_style(theme, CLASS_STYLE)?;
+    THEME_CSS
+        .set(css)
+        .map_err(|_| anyhow!("Highlight theme already set"))?;
+
+    Ok(())
+}
+
+pub(crate) fn highlight_css() -> &'static str {
+    THEME_CSS.get().map_or("", |c| c.as_str())
+}
+
+/// Replaces the code blocks in a markdown event stream with syntax highlighted HTML. The
+/// language comes from the first word of a fenced block's info string. Indented blocks and
+/// languages syntect doesn't know are still wrapped the same way, as plain text.
+pub(crate) fn highlight_code_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> impl Iterator<Item = Event<'a>> {
+    let mut code_block: Option<(Option<String>, String)> = None;
+    events.filter_map(move |event| match event {
+        Event::Start(Tag::CodeBlock(kind)) => {
+            let language = match kind {
+                CodeBlockKind::Fenced(info) => info
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .next()
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_owned),
+                CodeBlockKind::Indented => None,
+            };
+            code_block = Some((language, String::new()));
+            None
+        }
+        Event::End(TagEnd::CodeBlock) => {
+            let (language, code) = code_block.take()?;
+            Some(Event::Html(highlight(language.as_deref(), &code).into()))
+        }
+        Event::Text(text) if code_block.is_some() => {
+            if let Some((_, code)) = code_block.as_mut() {
+                code.push_str(&text);
+            }
+            None
+        }
+        e => Some(e),
+    })
+}
+
+fn highlight(language: Option<&str>, code: &str) -> String {
+    let syntax = language
+        .and_then(|l| SYNTAX_SET.find_syntax_by_token(l))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+    let mut highlighted = true;
+    for line in LinesWithEndings::from(code) {
+        if let Err(e) = generator.parse_html_for_line_which_includes_newline(line) {
+            warn!("Error highlighting code block: {}", e);
+            highlighted = false;
+            break;
+        }
+    }
+    let code_html = if highlighted {
+        generator.finalize()
+    } else {
+        // Rendering a lone text event just escapes it.
+        let mut escaped = String::with_capacity(code.len());
+        push_html(&mut escaped, std::iter::once(Event::Text(code.into())));
+        escaped
+    };
+    let language_class = language
+        .filter(|l| {
+            l.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_+#.".contains(c))
+        })
+        .map_or(String::new(), |l| format!(" class=\"language-{}\"", l));
+
+    format!(
+        "<pre class=\"hl-code\"><code{}>{}</code></pre>\n",
+        language_class, code_html
+    )
+}
@@ -1,12 +1,17 @@
 use std::{
+    collections::BTreeMap,
     env,
     net::SocketAddr,
     pin::Pin,
     str::FromStr,
     sync::{Arc, OnceLock, RwLock},
+    time::Duration,
 };
 
 use atom_syndication::Feed;
+use blog_atom::{BlogFeeds, CachedFeed};
+use blog_json_feed::{generate_json_feed, JsonFeed};
+use chrono::Utc;
 use hyper::{
     body::{Bytes, Incoming},
     server::conn::http1,
@@ -15,15 +20,32 @@ use hyper::{
 };
 use hyper_util::rt::TokioIo;
 use log::error;
-use sea_orm::{Database, DatabaseConnection, EntityTrait};
+use rss::Channel;
+use sea_orm::{ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter};
 #[cfg(not(target_env = "msvc"))]
 use tikv_jemallocator::Jemalloc;
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, sync::Notify};
 
+mod api_key_service;
+mod author_service;
 mod blog_atom;
+mod blog_json_feed;
+mod blog_metadata_service;
 mod blog_service;
 mod entity;
+mod highlight;
+mod math;
+mod page_service;
+mod podcast_rss;
+mod podcast_service;
+mod related_service;
+mod revision_service;
+mod sanitize;
+mod search_service;
+mod series_service;
 mod server;
+mod shortcode;
+mod tag_service;
 
 type GenericError = Box<dyn std::error::Error + Send + Sync>;
 type BoxResult<T> = std::result::Result<T, GenericError>;
@@ -39,15 +61,19 @@ type PinnedServiceFuture = Pin<
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 static SERVER_API_KEY: OnceLock<String> = OnceLock::new();
-static BASE_URL: OnceLock<String> = OnceLock::new();
+/// Longest the scheduled post publisher sleeps between checks.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60);
 
 #[tokio::main(worker_threads = 2)]
 async fn main() -> BoxResult<()> {
-    let (db_conn, atom_feed, listener) = initialize_service().await?;
+    let (db_conn, blog_feeds, podcast_feed, listener) = initialize_service().await?;
     let context = Context {
-        atom_feed: Arc::new(RwLock::new(atom_feed)),
+        blog_feeds: Arc::new(RwLock::new(blog_feeds)),
+        podcast_feed: Arc::new(RwLock::new(podcast_feed)),
         db: Arc::new(db_conn),
+        scheduler: Arc::new(Notify::new()),
     };
+    tokio::task::spawn(run_scheduler(context.clone()));
     let service = LazySusanService { ctx: context };
     loop {
         let (stream, _) = listener.accept().await?;
@@ -61,41 +87,121 @@ async fn main() -> BoxResult<()> {
     }
 }
 
-async fn initialize_service() -> BoxResult<(DatabaseConnection, Feed, TcpListener)> {
-    use crate::entity::rss_feeds::Entity as RssFeedEntity;
+async fn initialize_service() -> BoxResult<(
+    DatabaseConnection,
+    BTreeMap<i32, BlogFeeds>,
+    Channel,
+    TcpListener,
+)> {
+    use crate::entity::blog_metadata::Entity as BlogMetaEntity;
+    use crate::entity::rss_feeds::{Column as RssFeedColumn, Entity as RssFeedEntity};
+    use crate::entity::sea_orm_active_enums::{ContentType, FeedFormat};
 
     env_logger::init();
     dotenvy::dotenv().expect("Expected .env file in lazy_susan directory");
 
     let api_key = env::var("LS_API_KEY").expect("Expected LS_API_KEY variable in environment.");
-    let base_url = env::var("LS_BASE_URL").expect("Expected LS_BASE_URL variable in environment.");
     let db_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL variable in environment.");
     let ls_address = env::var("LS_ADDRESS").expect("Expected LS_ADDRESS variable in environment.");
     let ls_port = env::var("LS_PORT").expect("Expected LS_PORT variable in environment.");
+    let highlight_theme = env::var("LS_HIGHLIGHT_THEME")
+        .unwrap_or_else(|_| highlight::DEFAULT_HIGHLIGHT_THEME.to_owned());
     SERVER_API_KEY
-        .set(api_key)
+        .set(server::parse_server_api_key(&api_key)?)
         .expect("Error writing SERVER_API_KEY");
-    BASE_URL.set(base_url).expect("Error writing BASE_URL");
+    highlight::set_highlight_theme(&highlight_theme)?;
+    sanitize::set_sanitizer_allowlist(
+        &env::var("LS_ALLOWED_TAGS").unwrap_or_default(),
+        &env::var("LS_ALLOWED_ATTRIBUTES").unwrap_or_default(),
+    )?;
+    shortcode::set_shortcode_registry(shortcode::ShortcodeRegistry::with_builtins());
 
     let db_conn = Database::connect(db_url).await?;
     let addr_string = format!("{ls_address}:{ls_port}");
     let addr: SocketAddr = addr_string.parse().unwrap();
     let listener = TcpListener::bind(&addr).await?;
 
-    // Assumes single blog feed used by Lazy Susan.
-    let atom_string = RssFeedEntity::find_by_id(1)
+    let mut blog_feeds = BTreeMap::new();
+    for blog_metadata in BlogMetaEntity::find().all(&db_conn).await? {
+        let atom_feed = RssFeedEntity::find()
+            .filter(RssFeedColumn::BlogId.eq(blog_metadata.id))
+            .filter(RssFeedColumn::FeedFormat.eq(FeedFormat::Atom))
+            .one(&db_conn)
+            .await?
+            .map_or(
+                CachedFeed {
+                    xml: Feed::default().to_string().into(),
+                    ..Default::default()
+                },
+                |v| CachedFeed {
+                    xml: v.rss_xml_string.into(),
+                    last_updated: v.last_updated,
+                },
+            );
+        let rss_string = RssFeedEntity::find()
+            .filter(RssFeedColumn::BlogId.eq(blog_metadata.id))
+            .filter(RssFeedColumn::FeedFormat.eq(FeedFormat::Rss))
+            .one(&db_conn)
+            .await?
+            .map_or("".to_owned(), |v| v.rss_xml_string.to_owned());
+        let rss_feed = Channel::from_str(&rss_string).unwrap_or_default();
+        // The JSON feed isn't stored, so it's built fresh from the same data.
+        let json_feed = generate_json_feed(&db_conn, &blog_metadata)
+            .await
+            .unwrap_or_else(|e| {
+                error!("{}", e);
+                JsonFeed::default()
+            });
+        blog_feeds.insert(
+            blog_metadata.id,
+            BlogFeeds {
+                slug: blog_metadata.slug,
+                atom: atom_feed,
+                rss: rss_feed,
+                json: json_feed,
+            },
+        );
+    }
+    // Assumes a single podcast feed.
+    let podcast_string = RssFeedEntity::find()
+        .filter(RssFeedColumn::ContentType.eq(ContentType::Podcast))
         .one(&db_conn)
         .await?
         .map_or("".to_owned(), |v| v.rss_xml_string.to_owned());
-    let atom_feed = Feed::from_str(&atom_string).unwrap_or_default();
+    let podcast_feed = Channel::from_str(&podcast_string).unwrap_or_default();
+
+    Ok((db_conn, blog_feeds, podcast_feed, listener))
+}
 
-    Ok((db_conn, atom_feed, listener))
+/// Background task that publishes scheduled posts when they come due. Sleeps until the next
+/// scheduled post or for `SCHEDULER_INTERVAL`, whichever is sooner, and is woken early through
+/// `Context::scheduler` when a post is scheduled in the meantime.
+async fn run_scheduler(ctx: Context) {
+    loop {
+        let next_due = match blog_service::publish_scheduled_posts(&ctx).await {
+            Ok(n) => n,
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        };
+        let wait = next_due
+            .and_then(|n| (n.with_timezone(&Utc) - Utc::now()).to_std().ok())
+            .map_or(SCHEDULER_INTERVAL, |d| d.min(SCHEDULER_INTERVAL));
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = ctx.scheduler.notified() => {}
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct Context {
-    atom_feed: Arc<RwLock<Feed>>,
+    /// Each blog's feeds by blog id. The first is served at the routes without a blog in them.
+    blog_feeds: Arc<RwLock<BTreeMap<i32, BlogFeeds>>>,
+    podcast_feed: Arc<RwLock<Channel>>,
     db: Arc<DatabaseConnection>,
+    scheduler: Arc<Notify>,
 }
 
 #[derive(Debug, Clone)]
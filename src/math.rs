@@ -0,0 +1,214 @@
+use std::convert::Infallible;
+
+use log::warn;
+use pulldown_cmark::{html::push_html, CowStr, Event};
+use pulldown_latex::{
+    config::DisplayMode,
+    event::{Content, Event as LatexEvent},
+    push_mathml, Parser, RenderConfig, Storage,
+};
+
+/// MathML elements and attributes the renderer can produce, allowed through the sanitizer.
+pub(crate) const MATHML_TAGS: [&str; 30] = [
+    "math",
+    "annotation",
+    "semantics",
+    "merror",
+    "mfrac",
+    "mi",
+    "mmultiscripts",
+    "mn",
+    "mo",
+    "mover",
+    "mpadded",
+    "mphantom",
+    "mprescripts",
+    "mroot",
+    "mrow",
+    "ms",
+    "mspace",
+    "msqrt",
+    "mstyle",
+    "msub",
+    "msubsup",
+    "msup",
+    "mtable",
+    "mtd",
+    "mtext",
+    "mtr",
+    "munder",
+    "munderover",
+    "menclose",
+    "none",
+];
+pub(crate) const MATHML_ATTRIBUTES: [&str; 22] = [
+    "accent",
+    "accentunder",
+    "columnalign",
+    "depth",
+    "display",
+    "displaystyle",
+    "encoding",
+    "fence",
+    "form",
+    "height",
+    "largeop",
+    "linethickness",
+    "lspace",
+    "mathvariant",
+    "maxsize",
+    "minsize",
+    "movablelimits",
+    "rspace",
+    "scriptlevel",
+    "stretchy",
+    "symmetric",
+    "width",
+];
+
+/// Replaces the `$...$` and `$$...$$` math spans in a markdown event stream with MathML, so
+/// math displays without client-side JavaScript in browsers and feed readers alike. The TeX
+/// source is kept as an annotation.
+pub(crate) fn render_math<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> impl Iterator<Item = Event<'a>> {
+    events.map(|event| match event {
+        Event::InlineMath(tex) => Event::InlineHtml(tex_to_mathml(&tex, DisplayMode::Inline)),
+        Event::DisplayMath(tex) => Event::InlineHtml(tex_to_mathml(&tex, DisplayMode::Block)),
+        e => e,
+    })
+}
+
+fn tex_to_mathml(tex: &str, display_mode: DisplayMode) -> CowStr<'static> {
+    let storage = Storage::new();
+    let events = match Parser::new(tex, &storage).collect::<Result<Vec<LatexEvent>, _>>() {
+        Ok(events) => events,
+        Err(e) => {
+            warn!("Error parsing math: {}", e);
+            return math_fallback(tex, display_mode);
+        }
+    };
+    // pulldown-latex writes strings into the MathML as-is, so they're escaped beforehand. The
+    // only markup the parser puts in them is `&nbsp;`.
+    let escaped_strings = events
+        .iter()
+        .map(|event| match event {
+            LatexEvent::Content(Content::Text(s) | Content::Number(s) | Content::Function(s)) => {
+                escape_html(&s.replace("&nbsp;", "\u{A0}"))
+            }
+            _ => String::new(),
+        })
+        .collect::<Vec<String>>();
+    let events = events
+        .into_iter()
+        .zip(&escaped_strings)
+        .map(|(event, escaped)| {
+            Ok::<_, Infallible>(match event {
+                LatexEvent::Content(Content::Text(_)) => {
+                    LatexEvent::Content(Content::Text(escaped))
+                }
+                LatexEvent::Content(Content::Number(_)) => {
+                    LatexEvent::Content(Content::Number(escaped))
+                }
+                LatexEvent::Content(Content::Function(_)) => {
+                    LatexEvent::Content(Content::Function(escaped))
+                }
+                e => e,
+            })
+        });
+    let annotation = escape_html(tex);
+    let config = RenderConfig {
+        display_mode,
+        annotation: Some(&annotation),
+        xml: true,
+        ..Default::default()
+    };
+    let mut mathml = String::with_capacity(tex.len() * 8);
+    if let Err(e) = push_mathml(&mut mathml, events, config) {
+        warn!("Error rendering math: {}", e);
+        return math_fallback(tex, display_mode);
+    }
+
+    escape_text_nodes(&mathml).into()
+}
+
+/// Escapes the markup characters pulldown-latex writes into text nodes as-is, e.g. the `<`
+/// relation or `\&`. The strings were escaped before rendering, so the only raw characters left
+/// in text are single characters, each followed by the element's end tag or a combining mark.
+/// A `<` followed by a name or `/` starts a tag, and a `&` followed by a name or `#` and `;` is an
+/// entity already.
+fn escape_text_nodes(mathml: &str) -> String {
+    let mut escaped = String::with_capacity(mathml.len());
+    let mut in_tag = false;
+    for (i, c) in mathml.char_indices() {
+        let rest = &mathml[i + c.len_utf8()..];
+        match c {
+            '>' if in_tag => {
+                in_tag = false;
+                escaped.push(c);
+            }
+            _ if in_tag => escaped.push(c),
+            '<' if rest.starts_with(|n: char| n == '/' || n.is_ascii_alphabetic()) => {
+                in_tag = true;
+                escaped.push(c);
+            }
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' if is_entity(rest) => escaped.push(c),
+            '&' => escaped.push_str("&amp;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Whether the text after a `&` is the rest of an entity, e.g. `lt;` or `#x2061;`.
+fn is_entity(rest: &str) -> bool {
+    let name = rest.strip_prefix('#').unwrap_or(rest);
+    match name.split_once(';') {
+        Some((name, _)) => !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric()),
+        None => false,
+    }
+}
+
+/// The escaped TeX source in the same markup pulldown-cmark would use.
+fn math_fallback(tex: &str, display_mode: DisplayMode) -> CowStr<'static> {
+    let class = match display_mode {
+        DisplayMode::Inline => "math math-inline",
+        DisplayMode::Block => "math math-display",
+    };
+
+    format!("<span class=\"{}\">{}</span>", class, escape_html(tex)).into()
+}
+
+fn escape_html(text: &str) -> String {
+    // Rendering a lone text event just escapes it.
+    let mut escaped = String::with_capacity(text.len());
+    push_html(&mut escaped, std::iter::once(Event::Text(text.into())));
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup_characters() {
+        let mathml = tex_to_mathml(r"a < b > c \& \not< \text{<b>&}", DisplayMode::Inline);
+        assert!(mathml.contains("<mo>&lt;</mo>"));
+        assert!(mathml.contains("<mo>&gt;</mo>"));
+        assert!(mathml.contains("&amp;</mi>"));
+        assert!(mathml.contains("&lt;\u{338}</mo>"));
+        assert!(mathml.contains("&lt;b&gt;&amp;</mtext>"));
+        assert!(!mathml.contains("<b>"));
+    }
+
+    #[test]
+    fn keeps_tags_and_entities() {
+        assert_eq!(
+            escape_text_nodes("<mtext>&nbsp;a</mtext><mo>&</mo>"),
+            "<mtext>&nbsp;a</mtext><mo>&amp;</mo>"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Method, Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, NotSet,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::blog_atom::{render_page, TocEntry};
+use crate::blog_service::{POST_CACHE_CONTROL, PRIVATE_CACHE_CONTROL};
+use crate::entity::pages::{
+    ActiveModel as PageActive, Column as PageColumn, Entity as PageEntity, Model as Page,
+};
+use crate::{
+    server::{
+        api_key_auth, full, http_date, is_not_modified, not_modified, require_scope, ApiScope,
+    },
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our GET /pages handler that returns every page.
+#[derive(Serialize)]
+struct PageInfo {
+    title: String,
+    slug: String,
+    visible: bool,
+    last_updated: String,
+}
+
+impl From<Page> for PageInfo {
+    fn from(v: Page) -> Self {
+        Self {
+            title: v.title,
+            slug: v.slug,
+            visible: v.visible,
+            last_updated: v.last_updated.to_rfc3339(),
+        }
+    }
+}
+
+/// Utility struct for our GET /pages/[slug] handler that returns a page with its text rendered
+/// to HTML and the table of contents of its headings.
+#[derive(Serialize)]
+struct PageResponse {
+    #[serde(flatten)]
+    page: Page,
+    toc: Vec<TocEntry>,
+}
+
+/// Utility struct for our page write handler function.
+#[derive(Deserialize)]
+struct NewPageRequest {
+    title: String,
+    text: String,
+    visible: Option<bool>,
+}
+
+/// Utility struct for our page edit handler function.
+#[derive(Deserialize)]
+struct PageEditRequest {
+    title: Option<String>,
+    text: Option<String>,
+    visible: Option<bool>,
+}
+
+/// Routing function for everything under /api/pages/[slug].
+pub(crate) async fn handle_page_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3];
+    if (path_vec.len() != 4) || slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/pages/[slug]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    match *req.method() {
+        Method::GET => get_page(&ctx.db, &req, slug).await,
+        Method::POST => write_page(&ctx.db, req, slug).await,
+        Method::PUT => edit_page(&ctx.db, req, slug).await,
+        Method::DELETE => delete_page(&ctx.db, &req, slug).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /pages that returns every page, ordered by title. Hidden pages are
+/// only included for authenticated callers.
+pub(crate) async fn get_pages(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let mut query = PageEntity::find();
+    if !api_key_auth(db, req, ApiScope::ReadDrafts).await {
+        query = query.filter(PageColumn::Visible.eq(true));
+    }
+    let pages_vec = match query.order_by_asc(PageColumn::Title).all(db).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let pages_info: Vec<PageInfo> = pages_vec.into_iter().map(|p| p.into()).collect();
+    let json = serde_json::to_string(&pages_info).expect("Error converting page info vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /pages/[slug] that returns a page with its text rendered to HTML.
+/// Supports conditional requests, with validators derived from the page's `last_updated`.
+async fn get_page(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let mut page = match find_page(db, slug).await {
+        Ok(Some(p)) if p.visible || authenticated => p,
+        Ok(_) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+        Err(r) => return Ok(r),
+    };
+    let etag = format!("\"{}-{:x}\"", page.id, page.last_updated.timestamp_micros());
+    let last_modified = page.last_updated;
+    let cache_control = if page.visible {
+        POST_CACHE_CONTROL
+    } else {
+        PRIVATE_CACHE_CONTROL
+    };
+    if is_not_modified(req, &etag, &last_modified) {
+        return Ok(not_modified(&etag, &last_modified, cache_control));
+    }
+    let (html, toc) = render_page(&page.text);
+    page.text = html;
+    let json =
+        serde_json::to_string(&PageResponse { page, toc }).expect("Error converting page to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("ETag", etag)
+        .header("Last-Modified", http_date(&last_modified))
+        .header("Cache-Control", cache_control)
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /pages/[slug] that writes a new page at the slug. Pages are
+/// visible unless `visible` is false.
+async fn write_page(
+    db: &DatabaseConnection,
+    req: Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let new_page: NewPageRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    match find_page(db, slug).await {
+        Ok(Some(_)) => {
+            return Ok(Response::builder()
+                .status(StatusCode::CONFLICT)
+                .body(full(b"Error: Duplicate page slug".as_slice()))
+                .unwrap())
+        }
+        Ok(None) => {}
+        Err(r) => return Ok(r),
+    };
+    let page_active = PageActive {
+        id: NotSet,
+        slug: Set(slug.to_owned()),
+        title: Set(new_page.title),
+        text: Set(new_page.text),
+        visible: Set(new_page.visible.unwrap_or(true)),
+        last_updated: Set(FixedOffset::east_opt(0)
+            .unwrap()
+            .from_utc_datetime(&Utc::now().naive_utc())),
+    };
+    if let Err(e) = page_active.insert(db).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .body(full(b"Page successfully entered".as_slice()))
+        .unwrap())
+}
+
+async fn edit_page(
+    db: &DatabaseConnection,
+    req: Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let edits: PageEditRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(p) => p,
+        Err(e) => {
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    let mut page_active: PageActive = match find_page(db, slug).await {
+        Ok(Some(p)) => p.into(),
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+        Err(r) => return Ok(r),
+    };
+    if let Some(title) = edits.title {
+        page_active.title = Set(title);
+    }
+    if let Some(text) = edits.text {
+        page_active.text = Set(text);
+    }
+    if let Some(visible) = edits.visible {
+        page_active.visible = Set(visible);
+    }
+    page_active.last_updated = Set(FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc()));
+    if let Err(e) = page_active.update(db).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    let success_string = format!("Page successfully edited: {}", slug);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
+
+/// Handler function for DELETE /pages/[slug]. Unlike posts, pages have no history to keep, so
+/// they're removed from the database.
+async fn delete_page(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(db, req, ApiScope::DeletePosts).await {
+        return Ok(r);
+    }
+    let page = match find_page(db, slug).await {
+        Ok(Some(p)) => p,
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+        Err(r) => return Ok(r),
+    };
+    if let Err(e) = page.delete(db).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    let success_string = format!("Page successfully deleted: {}", slug);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
+
+async fn find_page(db: &DatabaseConnection, slug: &str) -> Result<Option<Page>, Response<BoxBody>> {
+    PageEntity::find()
+        .filter(PageColumn::Slug.eq(slug))
+        .one(db)
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap()
+        })
+}
@@ -0,0 +1,141 @@
+use anyhow::anyhow;
+use rss::{
+    extension::itunes::{ITunesCategory, ITunesChannelExtension, ITunesItemExtension, ITunesOwner},
+    Category, Channel, ChannelBuilder, Enclosure, Guid, Image, Item,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::blog_atom::render_page;
+use crate::entity::podcast_episodes::{
+    Column as PodcastEpisodeColumn, Entity as PodcastEpisodeEntity, Model as PodcastEpisode,
+};
+use crate::entity::podcasts::{Entity as PodcastEntity, Model as Podcast};
+use crate::BoxResult;
+
+/// Generates new podcast RSS feed with iTunes tags. Run on write operations for the podcast.
+/// Depends on the database having a single-row "podcasts" table for now.
+pub(crate) async fn generate_podcast_feed(db: &DatabaseConnection) -> BoxResult<Channel> {
+    let maybe_podcast = match PodcastEntity::find().one(db).await {
+        Ok(p) => p,
+        Err(e) => return Err(Box::new(e)),
+    };
+    let podcast = match maybe_podcast {
+        Some(p) => p,
+        None => return Err(anyhow!("Podcast metadata not in database.").into()),
+    };
+    let items: Vec<Item> = PodcastEpisodeEntity::find()
+        .filter(PodcastEpisodeColumn::Visible.eq(true))
+        .order_by_desc(PodcastEpisodeColumn::Date)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|e| episode_item(&podcast, e))
+        .collect();
+    let itunes_ext = ITunesChannelExtension {
+        author: Some(podcast.author.clone()),
+        categories: podcast
+            .category
+            .iter()
+            .map(|c| ITunesCategory {
+                text: c.clone(),
+                subcategory: None,
+            })
+            .collect(),
+        image: podcast.image_url.clone(),
+        explicit: Some(podcast.explicit.to_string()),
+        owner: podcast.owner_email.as_ref().map(|e| ITunesOwner {
+            name: Some(podcast.author.clone()),
+            email: Some(e.clone()),
+        }),
+        summary: Some(podcast.description.clone()),
+        r#type: Some("episodic".to_string()),
+        ..Default::default()
+    };
+    let image = podcast.image_url.as_ref().map(|url| Image {
+        url: url.clone(),
+        title: podcast.title.clone(),
+        link: podcast.link.clone(),
+        ..Default::default()
+    });
+    let categories: Vec<Category> = podcast
+        .category
+        .iter()
+        .map(|c| Category {
+            name: c.clone(),
+            domain: None,
+        })
+        .collect();
+    let mut channel_builder = ChannelBuilder::default();
+    let channel = channel_builder
+        .title(podcast.title.clone())
+        .link(podcast.link.clone())
+        .description(podcast.description.clone())
+        .language(Some(podcast.language.clone()))
+        .last_build_date(Some(chrono::Utc::now().to_rfc2822()))
+        .categories(categories)
+        .image(image)
+        .itunes_ext(Some(itunes_ext))
+        .items(items)
+        .build();
+
+    Ok(channel)
+}
+
+/// Builds an RSS item with an audio enclosure and iTunes tags for a single episode.
+fn episode_item(podcast: &Podcast, e: PodcastEpisode) -> Item {
+    let episode_url = format!("{}{}", &podcast.link, &e.slug);
+    let enclosure = Enclosure {
+        url: e.audio_url.clone(),
+        length: e.audio_size.to_string(),
+        mime_type: e.audio_type.clone(),
+    };
+    let guid = Guid {
+        value: episode_url.clone(),
+        permalink: true,
+    };
+    let categories: Vec<Category> = e
+        .tags
+        .iter()
+        .flatten()
+        .map(|t| Category {
+            name: t.clone(),
+            domain: None,
+        })
+        .collect();
+    let itunes_ext = ITunesItemExtension {
+        author: e.hosts.as_ref().map(|h| h.join(", ")),
+        duration: Some(format_duration(e.length)),
+        explicit: Some(e.explicit.to_string()),
+        summary: Some(e.description.clone()),
+        keywords: e.tags.as_ref().map(|t| t.join(",")),
+        season: e.season.map(|s| s.to_string()),
+        episode: e.episode.map(|n| n.to_string()),
+        episode_type: Some("full".to_string()),
+        ..Default::default()
+    };
+    let (parsed_html, _) = render_page(&e.text);
+
+    Item {
+        title: Some(e.title.clone()),
+        link: Some(episode_url),
+        description: Some(e.description.clone()),
+        categories,
+        enclosure: Some(enclosure),
+        guid: Some(guid),
+        pub_date: Some(e.date.to_rfc2822()),
+        content: Some(parsed_html),
+        itunes_ext: Some(itunes_ext),
+        ..Default::default()
+    }
+}
+
+/// Formats an episode length in seconds as the HH:MM:SS duration iTunes expects.
+fn format_duration(seconds: i32) -> String {
+    let seconds = seconds.max(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
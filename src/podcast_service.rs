@@ -0,0 +1,620 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, NotSet,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::podcast_episodes::{
+    ActiveModel as PodcastEpisodeActive, Column as PodcastEpisodeColumn,
+    Entity as PodcastEpisodeEntity, Model as PodcastEpisode,
+};
+use crate::entity::podcasts::{
+    ActiveModel as PodcastActive, Column as PodcastColumn, Entity as PodcastEntity,
+};
+use crate::entity::rss_feeds::{
+    ActiveModel as RssFeedActive, Column as RssFeedColumn, Entity as RssFeedEntity,
+};
+use crate::entity::sea_orm_active_enums::{ContentType, FeedFormat};
+use crate::podcast_rss::generate_podcast_feed;
+use crate::{
+    server::{api_key_auth, full, require_scope, ApiScope},
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our GET /podcasts handler that returns a sorted collection of all episodes.
+#[derive(Deserialize, Serialize)]
+struct PodcastEpisodeInfo {
+    title: String,
+    slug: String,
+    podcast_title: String,
+    date: String,
+    length: i32,
+    visible: bool,
+}
+
+impl From<PodcastEpisode> for PodcastEpisodeInfo {
+    fn from(v: PodcastEpisode) -> Self {
+        Self {
+            title: v.title,
+            slug: v.slug,
+            podcast_title: v.podcast_title,
+            date: v.date.to_rfc3339(),
+            length: v.length,
+            visible: v.visible,
+        }
+    }
+}
+
+/// Utility struct for our podcast episode edit handler function.
+#[derive(Deserialize)]
+struct EpisodeEditRequest {
+    title: Option<String>,
+    description: Option<String>,
+    text: Option<String>,
+    hosts: Option<Vec<String>>,
+    guests: Option<Vec<String>>,
+    audio_url: Option<String>,
+    audio_size: Option<i64>,
+    audio_type: Option<String>,
+    tags: Option<Vec<String>>,
+    length: Option<i32>,
+    season: Option<i32>,
+    episode: Option<i32>,
+    explicit: Option<bool>,
+    visible: Option<bool>,
+}
+
+/// Handler function for GET /podcasts that returns a sorted collection of all episodes. Hidden
+/// and deleted episodes are only included for authenticated callers.
+pub(crate) async fn get_podcast_episodes(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let mut query = PodcastEpisodeEntity::find();
+    if !api_key_auth(db, req, ApiScope::ReadDrafts).await {
+        query = query.filter(PodcastEpisodeColumn::Visible.eq(true));
+    }
+    let episodes_vec = match query
+        .order_by_desc(PodcastEpisodeColumn::Date)
+        .all(db)
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let episodes_info: Vec<PodcastEpisodeInfo> =
+        episodes_vec.into_iter().map(|e| e.into()).collect();
+    let json = serde_json::to_string(&episodes_info)
+        .expect("Error converting podcast episode info vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+pub(crate) async fn get_podcast_episode(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    use crate::blog_atom::render_page;
+
+    let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3];
+    if (path_vec.len() != 4) || slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/podcasts/[slug]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    let maybe_episode = match PodcastEpisodeEntity::find()
+        .filter(PodcastEpisodeColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(m) => m,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let mut episode = match maybe_episode {
+        Some(e) if e.visible => e,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+    };
+    // Rendered like a page, so it's sanitized the same way.
+    episode.text = render_page(&episode.text).0;
+    let json = serde_json::to_string(&episode).expect("Error converting podcast episode to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for writing podcast episodes into the database. Authenticates, parses
+/// request JSON, checks for a duplicate slug, writes the new episode and updates the podcast
+/// RSS feed.
+pub(crate) async fn write_podcast_episode(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let episode: PodcastEpisode = match serde_json::from_reader(whole_body.reader()) {
+        Ok(e) => e,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+
+    let maybe_duplicate = match PodcastEpisodeEntity::find()
+        .filter(PodcastEpisodeColumn::Slug.eq(&episode.slug))
+        .one(&*ctx.db)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if maybe_duplicate.is_some() {
+        return Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(full(b"Error: Duplicate slug/episode title".as_slice()))
+            .unwrap());
+    }
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Some(r) = set_podcast_updated(&txn, &episode.podcast_title).await {
+        return Ok(r);
+    };
+    let mut episode_active: PodcastEpisodeActive = episode.into();
+    // Let the database assign the id rather than inserting the deserialized default.
+    episode_active.id = NotSet;
+    let episode_returned = match episode_active.insert(&txn).await {
+        Ok(e) => e,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = update_podcast_rss(ctx).await {
+        return Ok(r);
+    };
+    let response_location = format!("/api/podcasts/{}", &episode_returned.slug);
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Location", response_location)
+        .body(full(b"Episode successfully entered".as_slice()))
+        .unwrap())
+}
+
+pub(crate) async fn edit_podcast_episode(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3].to_owned();
+    if (path_vec.len() != 4) || slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/podcasts/[slug]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let edits: EpisodeEditRequest = match serde_json::from_reader(whole_body.reader()) {
+        Ok(b) => b,
+        Err(e) => {
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    let maybe_episode = match PodcastEpisodeEntity::find()
+        .filter(PodcastEpisodeColumn::Slug.eq(slug))
+        .one(&*ctx.db)
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let episode = match maybe_episode {
+        Some(e) => e,
+        None => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+    };
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Some(r) = set_podcast_updated(&txn, &episode.podcast_title).await {
+        return Ok(r);
+    };
+    let mut episode_active: PodcastEpisodeActive = episode.into();
+    if let Some(title) = edits.title {
+        episode_active.title = Set(title);
+    }
+    if let Some(description) = edits.description {
+        episode_active.description = Set(description);
+    }
+    if let Some(text) = edits.text {
+        episode_active.text = Set(text);
+    }
+    if edits.hosts.is_some() {
+        episode_active.hosts = Set(edits.hosts);
+    }
+    if edits.guests.is_some() {
+        episode_active.guests = Set(edits.guests);
+    }
+    if let Some(audio_url) = edits.audio_url {
+        episode_active.audio_url = Set(audio_url);
+    }
+    if let Some(audio_size) = edits.audio_size {
+        episode_active.audio_size = Set(audio_size);
+    }
+    if let Some(audio_type) = edits.audio_type {
+        episode_active.audio_type = Set(audio_type);
+    }
+    if edits.tags.is_some() {
+        episode_active.tags = Set(edits.tags);
+    }
+    if let Some(length) = edits.length {
+        episode_active.length = Set(length);
+    }
+    if edits.season.is_some() {
+        episode_active.season = Set(edits.season);
+    }
+    if edits.episode.is_some() {
+        episode_active.episode = Set(edits.episode);
+    }
+    if let Some(explicit) = edits.explicit {
+        episode_active.explicit = Set(explicit);
+    }
+    if let Some(visible) = edits.visible {
+        episode_active.visible = Set(visible);
+    }
+    episode_active.edited = Set(true);
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    episode_active.last_updated = Set(now);
+    let episode_returned = match episode_active.update(&txn).await {
+        Ok(e) => e,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = update_podcast_rss(ctx).await {
+        return Ok(r);
+    };
+    let success_string = format!("Episode successfully edited: {}", &episode_returned.slug);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
+
+pub(crate) async fn delete_podcast_episode(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::DeletePosts).await {
+        return Ok(r);
+    }
+    let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3].to_owned();
+    if (path_vec.len() != 4) || slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/podcasts/[slug]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    let episode = match PodcastEpisodeEntity::find()
+        .filter(PodcastEpisodeColumn::Slug.eq(&slug))
+        .one(&*ctx.db)
+        .await
+    {
+        Ok(Some(e)) => e,
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Some(r) = set_podcast_updated(&txn, &episode.podcast_title).await {
+        return Ok(r);
+    };
+    let mut episode: PodcastEpisodeActive = episode.into();
+    episode.visible = Set(false);
+    if let Err(e) = episode.update(&txn).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = update_podcast_rss(ctx).await {
+        return Ok(r);
+    };
+    let success_string = format!("Episode successfully deleted: {}", &slug);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
+
+/// Regenerates the podcast RSS feed, stores it in the `rss_feeds` row with the Podcast content
+/// type (creating that row on first use), and swaps it into the cached feed.
+async fn update_podcast_rss(ctx: &Context) -> Option<Response<BoxBody>> {
+    let new_feed = match generate_podcast_feed(&ctx.db).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Error generating podcast RSS feed".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let rss_feed_model = match RssFeedEntity::find()
+        .filter(RssFeedColumn::ContentType.eq(ContentType::Podcast))
+        .one(&*ctx.db)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    let result = match rss_feed_model {
+        Some(r) => {
+            let mut rss_feed_model: RssFeedActive = r.into();
+            rss_feed_model.last_updated = Set(now);
+            rss_feed_model.rss_xml_string = Set(new_feed.to_string());
+            rss_feed_model.update(&*ctx.db).await
+        }
+        None => {
+            let rss_feed_model = RssFeedActive {
+                content_type: Set(Some(ContentType::Podcast)),
+                feed_format: Set(FeedFormat::Rss),
+                rss_xml_string: Set(new_feed.to_string()),
+                last_updated: Set(now),
+                ..Default::default()
+            };
+            rss_feed_model.insert(&*ctx.db).await
+        }
+    };
+    if let Err(e) = result {
+        error!("{}", e);
+        return Some(
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap(),
+        );
+    };
+    {
+        let mut feed = ctx.podcast_feed.write().unwrap();
+        *feed = new_feed;
+    }
+
+    None
+}
+
+pub(crate) async fn get_podcast_rss(ctx: &Context) -> BoxResult<Response<BoxBody>> {
+    let feed_string = {
+        ctx.podcast_feed
+            .read()
+            .expect("Error reading podcast RSS feed RwLock")
+            .to_string()
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/rss+xml")
+        .body(full(feed_string))
+        .unwrap())
+}
+
+/// Marks the podcast titled `podcast_title` as updated. Run in the same transaction as the
+/// episode write, so episodes can't be written for a podcast that doesn't exist.
+async fn set_podcast_updated<C: ConnectionTrait>(
+    db: &C,
+    podcast_title: &str,
+) -> Option<Response<BoxBody>> {
+    let maybe_podcast = match PodcastEntity::find()
+        .filter(PodcastColumn::Title.eq(podcast_title))
+        .one(db)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            return Some(
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Database error".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let mut podcast: PodcastActive = match maybe_podcast {
+        Some(p) => p.into(),
+        None => {
+            return Some(
+                Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .header("Content-Type", "text/plain")
+                    .body(full(b"Bad request: Podcast not found".as_slice()))
+                    .unwrap(),
+            );
+        }
+    };
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    podcast.last_updated = Set(now);
+    if let Err(e) = podcast.update(db).await {
+        error!("{}", e);
+        return Some(
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap(),
+        );
+    };
+
+    None
+}
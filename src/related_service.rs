@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+use hyper::{
+    body::{Bytes, Incoming},
+    Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, FromQueryResult, QueryFilter,
+    Statement,
+};
+
+use crate::blog_service::BlogPostInfo;
+use crate::entity::blog_posts::{
+    Column as BlogPostColumn, Entity as BlogPostEntity, Model as BlogPost,
+};
+use crate::{
+    server::{api_key_auth, full, ApiScope},
+    BoxBody, BoxResult,
+};
+
+/// Number of related posts GET /posts/[slug]/related returns.
+const RELATED_LIMIT: i64 = 5;
+
+/// Serialized related posts by post id. Any post write can change every post's related posts,
+/// so the whole cache is cleared on writes.
+static RELATED_CACHE: LazyLock<RwLock<HashMap<i32, Bytes>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// A ranked row from the related posts query.
+#[derive(FromQueryResult)]
+struct RelatedRow {
+    id: i32,
+}
+
+/// Handler function for GET /posts/[slug]/related that returns up to `RELATED_LIMIT` other
+/// visible posts from the same blog, most related first, in the same format as GET /posts.
+/// Posts score a point for each tag they share with this one, plus their full-text rank against
+/// the words in this post's title and description. Posts with nothing in common aren't returned.
+pub(crate) async fn get_related_posts(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path_vec = &req.uri().path().split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3];
+    if (path_vec.len() != 5) || slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/posts/[slug]/related'".as_slice(),
+            ))
+            .unwrap());
+    }
+    let maybe_post = match BlogPostEntity::find()
+        .filter(BlogPostColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(m) => m,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let post = match maybe_post {
+        Some(p) if p.visible || authenticated => p,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(full(b"Not Found".as_slice()))
+                .unwrap())
+        }
+    };
+    let cached = RELATED_CACHE
+        .read()
+        .expect("Error reading related posts cache RwLock")
+        .get(&post.id)
+        .cloned();
+    let json = match cached {
+        Some(j) => j,
+        None => {
+            let related_vec = match find_related_posts(db, post.id).await {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("{}", e);
+                    return Ok(Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .header("Content-Type", "text/plain")
+                        .body(full(b"Database error".as_slice()))
+                        .unwrap());
+                }
+            };
+            let related_info: Vec<BlogPostInfo> =
+                related_vec.into_iter().map(|p| p.into()).collect();
+            let json: Bytes = serde_json::to_string(&related_info)
+                .expect("Error converting related post info vec to JSON")
+                .into();
+            RELATED_CACHE
+                .write()
+                .expect("Error writing related posts cache RwLock")
+                .insert(post.id, json.clone());
+
+            json
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Ranks the posts related to the post with the given id, most related first. Its title and
+/// description words are ORed together into a query to rank other posts' text against.
+async fn find_related_posts(db: &DatabaseConnection, id: i32) -> Result<Vec<BlogPost>, DbErr> {
+    let ranked = RelatedRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"WITH source AS (
+                SELECT id, blog_id, tags,
+                    coalesce((
+                        SELECT string_agg(
+                            '''' || replace(replace(lexeme, '\', '\\'), '''', '''''') || '''',
+                            ' | '
+                        )
+                        FROM unnest(tsvector_to_array(ts_filter(search_vector, '{a,b}')))
+                            AS lexeme
+                    ), '')::tsquery AS query
+                FROM blog_posts
+                WHERE id = $1
+            )
+            SELECT blog_posts.id
+            FROM blog_posts, source,
+                LATERAL (
+                    SELECT cardinality(ARRAY(
+                        SELECT unnest(blog_posts.tags)
+                        INTERSECT SELECT unnest(source.tags)
+                    )) + ts_rank(blog_posts.search_vector, source.query) AS score
+                ) AS related
+            WHERE blog_posts.visible
+                AND blog_posts.blog_id = source.blog_id
+                AND blog_posts.id <> source.id
+                AND related.score > 0
+            ORDER BY related.score DESC, blog_posts.date DESC
+            LIMIT $2"#,
+        [id.into(), RELATED_LIMIT.into()],
+    ))
+    .all(db)
+    .await?;
+    let ids: Vec<i32> = ranked.into_iter().map(|r| r.id).collect();
+    let mut posts = BlogPostEntity::find()
+        .filter(BlogPostColumn::Id.is_in(ids.clone()))
+        .all(db)
+        .await?;
+    posts.sort_by_key(|p| ids.iter().position(|&id| id == p.id));
+
+    Ok(posts)
+}
+
+/// Forgets every cached list of related posts. Called whenever posts are written.
+pub(crate) fn clear_related_cache() {
+    RELATED_CACHE
+        .write()
+        .expect("Error writing related posts cache RwLock")
+        .clear();
+}
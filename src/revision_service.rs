@@ -0,0 +1,391 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use hyper::{body::Incoming, Method, Request, Response, StatusCode};
+use log::error;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::blog_atom::generate_description;
+use crate::blog_service::{has_generated_description, set_blog_updated, update_blog_rss};
+use crate::entity::blog_post_revisions::{
+    ActiveModel as BlogPostRevisionActive, Column as BlogPostRevisionColumn,
+    Entity as BlogPostRevisionEntity, Model as BlogPostRevision,
+};
+use crate::entity::blog_posts::{
+    ActiveModel as BlogPostActive, Column as BlogPostColumn, Entity as BlogPostEntity,
+    Model as BlogPost,
+};
+use crate::{
+    server::{api_key_auth, full, require_scope, ApiScope},
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our GET /posts/[slug]/revisions handler that lists a post's revisions.
+#[derive(Serialize)]
+struct RevisionInfo {
+    revision: i32,
+    title: String,
+    created_at: String,
+}
+
+impl From<BlogPostRevision> for RevisionInfo {
+    fn from(v: BlogPostRevision) -> Self {
+        Self {
+            revision: v.revision,
+            title: v.title,
+            created_at: v.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Utility struct for our revision diff handler.
+#[derive(Serialize)]
+struct RevisionDiff {
+    from: i32,
+    to: i32,
+    from_title: String,
+    to_title: String,
+    diff: String,
+}
+
+/// Routing function for everything under /api/posts/[slug]/revisions.
+pub(crate) async fn handle_revision_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3];
+    match (req.method(), &path_vec[5..]) {
+        (&Method::GET, []) => get_revisions(&ctx.db, &req, slug).await,
+        (&Method::GET, [n]) => get_revision(&ctx.db, &req, slug, n).await,
+        (&Method::GET, [from, "diff", to]) => diff_revisions(&ctx.db, &req, slug, from, to).await,
+        (&Method::POST, [n, "rollback"]) => rollback_revision(ctx, &req, slug, n).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /posts/[slug]/revisions that returns a post's revisions, oldest
+/// first.
+async fn get_revisions(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let post = match find_post(db, slug, authenticated).await {
+        Ok(p) => p,
+        Err(r) => return Ok(r),
+    };
+    let revisions_vec = match BlogPostRevisionEntity::find()
+        .filter(BlogPostRevisionColumn::PostId.eq(post.id))
+        .order_by_asc(BlogPostRevisionColumn::Revision)
+        .all(db)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let revisions_info: Vec<RevisionInfo> = revisions_vec.into_iter().map(|r| r.into()).collect();
+    let json =
+        serde_json::to_string(&revisions_info).expect("Error converting revision info vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /posts/[slug]/revisions/[n] that returns a single revision with
+/// its markdown source.
+async fn get_revision(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+    n: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let post = match find_post(db, slug, authenticated).await {
+        Ok(p) => p,
+        Err(r) => return Ok(r),
+    };
+    let revision = match find_revision(db, post.id, n).await {
+        Ok(r) => r,
+        Err(r) => return Ok(r),
+    };
+    let json = serde_json::to_string(&revision).expect("Error converting revision to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /posts/[slug]/revisions/[from]/diff/[to] that returns a unified
+/// diff of the markdown text between two revisions.
+async fn diff_revisions(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+    from: &str,
+    to: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let post = match find_post(db, slug, authenticated).await {
+        Ok(p) => p,
+        Err(r) => return Ok(r),
+    };
+    let from_revision = match find_revision(db, post.id, from).await {
+        Ok(r) => r,
+        Err(r) => return Ok(r),
+    };
+    let to_revision = match find_revision(db, post.id, to).await {
+        Ok(r) => r,
+        Err(r) => return Ok(r),
+    };
+    let text_diff = TextDiff::from_lines(&from_revision.text, &to_revision.text);
+    let diff = text_diff
+        .unified_diff()
+        .context_radius(3)
+        .header(
+            &format!("revision {}", from_revision.revision),
+            &format!("revision {}", to_revision.revision),
+        )
+        .to_string();
+    let revision_diff = RevisionDiff {
+        from: from_revision.revision,
+        to: to_revision.revision,
+        from_title: from_revision.title,
+        to_title: to_revision.title,
+        diff,
+    };
+    let json = serde_json::to_string(&revision_diff).expect("Error converting diff to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /posts/[slug]/revisions/[n]/rollback. Restores the revision's
+/// title, text and tags, records the result as a new revision, and updates Atom syndication
+/// XML. Nothing is changed if the post already matches the revision.
+async fn rollback_revision(
+    ctx: &Context,
+    req: &Request<Incoming>,
+    slug: &str,
+    n: &str,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let post = match find_post(&ctx.db, slug, true).await {
+        Ok(p) => p,
+        Err(r) => return Ok(r),
+    };
+    let revision = match find_revision(&ctx.db, post.id, n).await {
+        Ok(r) => r,
+        Err(r) => return Ok(r),
+    };
+    if (&post.title, &post.text, &post.tags) == (&revision.title, &revision.text, &revision.tags) {
+        let unchanged_string = format!("Post already matches revision {}: {}", n, &post.slug);
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(full(unchanged_string))
+            .unwrap());
+    }
+    let previous = post.clone();
+    let mut blog_post_active: BlogPostActive = post.into();
+    // Generated descriptions follow the restored text, hand-written ones are kept.
+    if has_generated_description(&previous) {
+        blog_post_active.description = Set(generate_description(&revision.text));
+    }
+    blog_post_active.title = Set(revision.title);
+    blog_post_active.text = Set(revision.text);
+    blog_post_active.tags = Set(revision.tags);
+    blog_post_active.edited = Set(true);
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    blog_post_active.last_updated = Set(now);
+    let txn = match ctx.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let blog_post_returned = match blog_post_active.update(&txn).await {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if let Err(e) = record_revision(&txn, Some(&previous), &blog_post_returned).await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Err(e) = txn.commit().await {
+        error!("{}", e);
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Database error".as_slice()))
+            .unwrap());
+    }
+    if let Some(r) = update_blog_rss(ctx, blog_post_returned.blog_id).await {
+        return Ok(r);
+    };
+    if let Some(r) = set_blog_updated(&ctx.db, blog_post_returned.blog_id).await {
+        return Ok(r);
+    };
+    let success_string = format!(
+        "Post successfully rolled back to revision {}: {}",
+        n, &blog_post_returned.slug
+    );
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(full(success_string))
+        .unwrap())
+}
+
+/// Stores the post's current title, text and tags as its next revision. If the post has no
+/// history yet and `previous` is given, that is stored first so the pre-edit version of posts
+/// written before revisions existed isn't lost.
+pub(crate) async fn record_revision<C: ConnectionTrait>(
+    db: &C,
+    previous: Option<&BlogPost>,
+    post: &BlogPost,
+) -> Result<(), DbErr> {
+    let latest = BlogPostRevisionEntity::find()
+        .filter(BlogPostRevisionColumn::PostId.eq(post.id))
+        .order_by_desc(BlogPostRevisionColumn::Revision)
+        .one(db)
+        .await?;
+    let mut next_revision = latest.map_or(1, |r| r.revision + 1);
+    if let (1, Some(p)) = (next_revision, previous) {
+        revision_from_post(p, next_revision).insert(db).await?;
+        next_revision += 1;
+    }
+    revision_from_post(post, next_revision).insert(db).await?;
+
+    Ok(())
+}
+
+fn revision_from_post(post: &BlogPost, revision: i32) -> BlogPostRevisionActive {
+    BlogPostRevisionActive {
+        post_id: Set(post.id),
+        revision: Set(revision),
+        title: Set(post.title.clone()),
+        text: Set(post.text.clone()),
+        tags: Set(post.tags.clone()),
+        created_at: Set(post.last_updated),
+        ..Default::default()
+    }
+}
+
+/// Looks up the post a revision request refers to. Posts that aren't visible are only found
+/// for authenticated callers. Returns the response to send instead if there's no such post.
+async fn find_post(
+    db: &DatabaseConnection,
+    slug: &str,
+    authenticated: bool,
+) -> Result<BlogPost, Response<BoxBody>> {
+    let maybe_post = match BlogPostEntity::find()
+        .filter(BlogPostColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(m) => m,
+        Err(e) => {
+            error!("{}", e);
+            return Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    match maybe_post {
+        Some(p) if p.visible || authenticated => Ok(p),
+        _ => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Looks up revision `n` of a post. Returns the response to send instead if `n` isn't a number
+/// or there's no such revision.
+async fn find_revision(
+    db: &DatabaseConnection,
+    post_id: i32,
+    n: &str,
+) -> Result<BlogPostRevision, Response<BoxBody>> {
+    let revision_number: i32 = match n.parse() {
+        Ok(r) => r,
+        Err(_) => {
+            return Err(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("Content-Type", "text/plain")
+                .body(full(
+                    b"Bad request: Revision should be an integer".as_slice(),
+                ))
+                .unwrap());
+        }
+    };
+    let maybe_revision = match BlogPostRevisionEntity::find()
+        .filter(BlogPostRevisionColumn::PostId.eq(post_id))
+        .filter(BlogPostRevisionColumn::Revision.eq(revision_number))
+        .one(db)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    match maybe_revision {
+        Some(r) => Ok(r),
+        None => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
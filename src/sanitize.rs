@@ -0,0 +1,72 @@
+use std::sync::{LazyLock, OnceLock};
+
+use ammonia::Builder;
+use anyhow::anyhow;
+
+use crate::math::{MATHML_ATTRIBUTES, MATHML_TAGS};
+use crate::shortcode::SHORTCODE_TAG_ATTRIBUTES;
+use crate::BoxResult;
+
+/// Attributes allowed in rendered posts on top of ammonia's defaults, which cover everything
+/// plain markdown produces. These and the MathML and shortcode allowlists cover what our
+/// renderer adds: heading anchors, the classes on highlighted code, footnotes and callouts, math
+/// and media players.
+const RENDERER_ATTRIBUTES: [&str; 2] = ["class", "id"];
+
+/// Tags whose content ammonia always removes, and the attribute it sets on links itself.
+/// Allowing them makes it panic while rendering, so they're refused at startup.
+const FORBIDDEN_TAGS: [&str; 2] = ["script", "style"];
+const FORBIDDEN_ATTRIBUTES: [&str; 1] = ["rel"];
+
+/// Extra tags and attributes from the environment. See `set_sanitizer_allowlist`.
+static EXTRA_ALLOWLIST: OnceLock<(Vec<String>, Vec<String>)> = OnceLock::new();
+
+static SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(|| {
+    let (extra_tags, extra_attributes) = EXTRA_ALLOWLIST.get_or_init(Default::default);
+    let mut builder = Builder::default();
+    builder
+        .add_tags(MATHML_TAGS)
+        .add_tags(extra_tags.iter().map(String::as_str))
+        .add_generic_attributes(RENDERER_ATTRIBUTES)
+        .add_generic_attributes(MATHML_ATTRIBUTES)
+        .add_generic_attributes(extra_attributes.iter().map(String::as_str));
+    for (tag, attributes) in SHORTCODE_TAG_ATTRIBUTES {
+        builder
+            .add_tags([tag])
+            .add_tag_attributes(tag, attributes.iter().copied());
+    }
+
+    builder
+});
+
+/// Adds tags, and attributes allowed on any tag, to the sanitizer's allowlist. Each is a
+/// comma-separated list. Run once at startup, before anything is rendered.
+pub(crate) fn set_sanitizer_allowlist(tags: &str, attributes: &str) -> BoxResult<()> {
+    let split = |list: &str| {
+        list.split(',')
+            .map(|s| s.trim().to_ascii_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<String>>()
+    };
+    let (tags, attributes) = (split(tags), split(attributes));
+    if let Some(tag) = tags.iter().find(|t| FORBIDDEN_TAGS.contains(&t.as_str())) {
+        return Err(anyhow!("LS_ALLOWED_TAGS can't allow \"{}\"", tag).into());
+    }
+    if let Some(attribute) = attributes
+        .iter()
+        .find(|a| FORBIDDEN_ATTRIBUTES.contains(&a.as_str()))
+    {
+        return Err(anyhow!("LS_ALLOWED_ATTRIBUTES can't allow \"{}\"", attribute).into());
+    }
+    EXTRA_ALLOWLIST
+        .set((tags, attributes))
+        .map_err(|_| anyhow!("Sanitizer allowlist already set"))?;
+
+    Ok(())
+}
+
+/// Strips anything not on the allowlist from rendered HTML, e.g. scripts, event handler
+/// attributes and `javascript:` URLs from raw HTML in a post's markdown.
+pub(crate) fn sanitize_html(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
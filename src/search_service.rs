@@ -0,0 +1,158 @@
+use hyper::{body::Incoming, Request, Response, StatusCode};
+use log::error;
+use pulldown_cmark_escape::escape_html;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, DatabaseConnection, DbBackend, FromQueryResult, Statement,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::sea_orm_active_enums::PostStatus;
+use crate::{server::full, BoxBody, BoxResult};
+
+/// Number of results GET /search returns when the caller doesn't ask for a limit, and the most
+/// it will return.
+const DEFAULT_SEARCH_LIMIT: u64 = 20;
+const MAX_SEARCH_LIMIT: u64 = 50;
+
+/// Control characters `ts_headline` marks matches with. They're stripped from the post text
+/// beforehand, so the snippet can be HTML-escaped and these swapped for `<mark>` tags after.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// Query parameters accepted by our GET /search handler.
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<u64>,
+}
+
+/// A ranked row from the full-text search query.
+#[derive(FromQueryResult)]
+struct SearchRow {
+    title: String,
+    slug: String,
+    date: DateTimeWithTimeZone,
+    visible: bool,
+    status: PostStatus,
+    rank: f32,
+    snippet: String,
+}
+
+/// Utility struct for our GET /search handler. Like `BlogPostInfo` with the result's rank and a
+/// snippet of the post text, HTML-escaped, with matches wrapped in `<mark>` tags.
+#[derive(Serialize)]
+struct SearchResult {
+    title: String,
+    slug: String,
+    date: String,
+    visible: bool,
+    status: PostStatus,
+    rank: f32,
+    snippet: String,
+}
+
+impl From<SearchRow> for SearchResult {
+    fn from(v: SearchRow) -> Self {
+        Self {
+            title: v.title,
+            slug: v.slug,
+            date: v.date.to_rfc3339(),
+            visible: v.visible,
+            status: v.status,
+            rank: v.rank,
+            snippet: highlighted_snippet(&v.snippet),
+        }
+    }
+}
+
+/// Handler function for GET /search that runs a full-text search over visible posts' titles,
+/// descriptions and text, returning results best match first. `q` takes web search syntax, e.g.
+/// quoted phrases, `or` and `-excluded`.
+pub(crate) async fn search_blog_posts(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let params: SearchQuery = match serde_urlencoded::from_str(req.uri().query().unwrap_or("")) {
+        Ok(p) => p,
+        Err(e) => {
+            let err_string = format!("Bad request: Invalid query parameters: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("Content-Type", "text/plain")
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    if params.q.trim().is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Bad request: Search query 'q' is empty".as_slice()))
+            .unwrap());
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let results_vec = match SearchRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT title, slug, date, visible, status::text AS status,
+                ts_rank(search_vector, query) AS rank,
+                ts_headline('english', translate(text, chr(2) || chr(3), ''), query,
+                    'StartSel=' || chr(2) || ', StopSel=' || chr(3)
+                        || ', MaxFragments=2, MaxWords=30, MinWords=10')
+                    AS snippet
+            FROM blog_posts, websearch_to_tsquery('english', $1) AS query
+            WHERE visible AND search_vector @@ query
+            ORDER BY rank DESC, date DESC
+            LIMIT $2"#,
+        [params.q.into(), (limit as i64).into()],
+    ))
+    .all(db)
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let results: Vec<SearchResult> = results_vec.into_iter().map(|r| r.into()).collect();
+    let json = serde_json::to_string(&results).expect("Error converting search result vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Escapes the snippet's text so raw HTML in a post can't get through, then wraps its matches
+/// in `<mark>` tags.
+fn highlighted_snippet(snippet: &str) -> String {
+    let mut escaped = String::with_capacity(snippet.len());
+    // Writing to a String can't fail.
+    let _ = escape_html(&mut escaped, snippet);
+
+    escaped
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_snippet_and_marks_matches() {
+        let snippet = format!("<b>{}cats{}</b> & dogs", MATCH_START, MATCH_END);
+        assert_eq!(
+            highlighted_snippet(&snippet),
+            "&lt;b&gt;<mark>cats</mark>&lt;/b&gt; &amp; dogs"
+        );
+    }
+}
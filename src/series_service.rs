@@ -0,0 +1,333 @@
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Buf, Incoming},
+    Method, Request, Response, StatusCode,
+};
+use log::error;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, NotSet, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Serialize;
+
+use crate::blog_atom::generate_filtered_atom_feed;
+use crate::blog_service::{BlogPostInfo, PostLink};
+use crate::entity::blog_posts::{
+    Column as BlogPostColumn, Entity as BlogPostEntity, Model as BlogPost,
+};
+use crate::entity::sea_orm_active_enums::PostStatus;
+use crate::entity::series::{
+    ActiveModel as SeriesActive, Column as SeriesColumn, Entity as SeriesEntity, Model as Series,
+};
+use crate::{
+    server::{api_key_auth, full, require_scope, ApiScope},
+    BoxBody, BoxResult, Context,
+};
+
+/// Utility struct for our GET /series/[slug] handler that returns a series with its posts in
+/// order.
+#[derive(Serialize)]
+struct SeriesResponse {
+    #[serde(flatten)]
+    series: Series,
+    posts: Vec<BlogPostInfo>,
+}
+
+/// A post's place in its series, returned with GET /posts/[slug]. `position` counts from 1
+/// among the parts the caller can see, and `previous` and `next` are the parts either side.
+#[derive(Serialize)]
+pub(crate) struct SeriesInfo {
+    title: String,
+    slug: String,
+    position: usize,
+    total: usize,
+    previous: Option<PostLink>,
+    next: Option<PostLink>,
+}
+
+/// Routing function for everything under /api/series/[slug].
+pub(crate) async fn handle_series_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let slug = path_vec[3];
+    if slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/series/[slug]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    match (req.method(), &path_vec[4..]) {
+        (&Method::GET, []) => get_series_posts(&ctx.db, &req, slug).await,
+        (&Method::GET, ["atom"]) => get_series_atom(&ctx.db, slug, &path).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /series that returns every series, ordered by title.
+pub(crate) async fn get_series_list(db: &DatabaseConnection) -> BoxResult<Response<BoxBody>> {
+    let series_vec = match SeriesEntity::find()
+        .order_by_asc(SeriesColumn::Title)
+        .all(db)
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let json = serde_json::to_string(&series_vec).expect("Error converting series vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for POST /series that creates a series. Posts are added to it through
+/// their `series_id`.
+pub(crate) async fn write_series(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    if let Some(r) = require_scope(&ctx.db, &req, ApiScope::WritePosts).await {
+        return Ok(r);
+    }
+    let whole_body = req.collect().await?.aggregate();
+    let series: Series = match serde_json::from_reader(whole_body.reader()) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{}", e);
+            let err_string = format!("Request contained malformed JSON: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(full(err_string))
+                .unwrap());
+        }
+    };
+    if series.slug.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(full(b"Bad request: Series slug is empty".as_slice()))
+            .unwrap());
+    }
+    let maybe_duplicate = match SeriesEntity::find()
+        .filter(SeriesColumn::Slug.eq(&series.slug))
+        .one(&*ctx.db)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    if maybe_duplicate.is_some() {
+        return Ok(Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(full(b"Error: Duplicate series slug".as_slice()))
+            .unwrap());
+    }
+    let mut series_active: SeriesActive = series.into();
+    // Let the database assign the id rather than inserting the deserialized default.
+    series_active.id = NotSet;
+    let series_returned = match series_active.insert(&*ctx.db).await {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let json = serde_json::to_string(&series_returned).expect("Error converting series to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /series/[slug] that returns the series with its posts in series
+/// order. Drafts and scheduled posts are only included for authenticated callers.
+async fn get_series_posts(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    slug: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let series = match find_series(db, slug).await {
+        Ok(s) => s,
+        Err(r) => return Ok(r),
+    };
+    let authenticated = api_key_auth(db, req, ApiScope::ReadDrafts).await;
+    let posts_vec = match series_parts(db, series.id, authenticated).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let series_response = SeriesResponse {
+        series,
+        posts: posts_vec.into_iter().map(|p| p.into()).collect(),
+    };
+    let json = serde_json::to_string(&series_response).expect("Error converting series to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /series/[slug]/atom that returns an Atom feed of the series' posts.
+/// Generated on request rather than cached like the main feed.
+async fn get_series_atom(
+    db: &DatabaseConnection,
+    slug: &str,
+    path: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let series = match find_series(db, slug).await {
+        Ok(s) => s,
+        Err(r) => return Ok(r),
+    };
+    let condition = Condition::all().add(BlogPostColumn::SeriesId.eq(series.id));
+    let feed = match generate_filtered_atom_feed(db, None, condition, Some(path)).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Error generating Atom feed".as_slice()))
+                .unwrap());
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/atom+xml")
+        .body(full(feed.to_string()))
+        .unwrap())
+}
+
+/// Returns the post's place in its series, if it's in one the caller can see it in.
+pub(crate) async fn series_info(
+    db: &DatabaseConnection,
+    post: &BlogPost,
+    authenticated: bool,
+) -> Result<Option<SeriesInfo>, DbErr> {
+    let Some(series_id) = post.series_id else {
+        return Ok(None);
+    };
+    let Some(series) = SeriesEntity::find_by_id(series_id).one(db).await? else {
+        return Ok(None);
+    };
+    let parts = series_parts(db, series_id, authenticated).await?;
+    let Some(index) = parts.iter().position(|p| p.id == post.id) else {
+        return Ok(None);
+    };
+
+    Ok(Some(SeriesInfo {
+        title: series.title,
+        slug: series.slug,
+        position: index + 1,
+        total: parts.len(),
+        previous: index.checked_sub(1).map(|i| PostLink::from(&parts[i])),
+        next: parts.get(index + 1).map(PostLink::from),
+    }))
+}
+
+/// Checks a post's series exists and works out its position in it. Posts without a position
+/// are added to the end of the series. Returns `None` if there's no such series.
+pub(crate) async fn resolve_series_position<C: ConnectionTrait>(
+    db: &C,
+    series_id: i32,
+    position: Option<i32>,
+) -> Result<Option<i32>, DbErr> {
+    if SeriesEntity::find_by_id(series_id).one(db).await?.is_none() {
+        return Ok(None);
+    }
+    if let Some(p) = position {
+        return Ok(Some(p));
+    }
+    let last_position = BlogPostEntity::find()
+        .select_only()
+        .column_as(BlogPostColumn::SeriesPosition.max(), "last_position")
+        .filter(BlogPostColumn::SeriesId.eq(series_id))
+        .into_tuple::<Option<i32>>()
+        .one(db)
+        .await?
+        .flatten();
+
+    Ok(Some(last_position.map_or(1, |p| p + 1)))
+}
+
+/// A series' posts in series order, ties broken by date. Drafts and scheduled posts are only
+/// included when `authenticated`, and deleted posts never are.
+async fn series_parts(
+    db: &DatabaseConnection,
+    series_id: i32,
+    authenticated: bool,
+) -> Result<Vec<BlogPost>, DbErr> {
+    let mut query = BlogPostEntity::find().filter(BlogPostColumn::SeriesId.eq(series_id));
+    query = if authenticated {
+        query.filter(BlogPostColumn::Status.ne(PostStatus::Deleted))
+    } else {
+        query.filter(BlogPostColumn::Visible.eq(true))
+    };
+
+    query
+        .order_by_asc(BlogPostColumn::SeriesPosition)
+        .order_by_asc(BlogPostColumn::Date)
+        .all(db)
+        .await
+}
+
+async fn find_series(db: &DatabaseConnection, slug: &str) -> Result<Series, Response<BoxBody>> {
+    match SeriesEntity::find()
+        .filter(SeriesColumn::Slug.eq(slug))
+        .one(db)
+        .await
+    {
+        Ok(Some(s)) => Ok(s),
+        Ok(None) => Err(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+        Err(e) => {
+            error!("{}", e);
+            Err(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap())
+        }
+    }
+}
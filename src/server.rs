@@ -1,11 +1,59 @@
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use http_body_util::{BodyExt, Full};
 use hyper::{
     body::{Bytes, Incoming},
-    header::HeaderValue,
-    Request,
+    header::{AUTHORIZATION, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    Request, Response, StatusCode,
 };
+use log::error;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+use crate::entity::api_keys::{Column as ApiKeyColumn, Entity as ApiKeyEntity};
+use crate::{BoxBody, BoxResult, SERVER_API_KEY};
+
+/// What an API key is allowed to do. `Admin` covers every other scope, along with managing API
+/// keys and blog metadata.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ApiScope {
+    ReadDrafts,
+    WritePosts,
+    DeletePosts,
+    Admin,
+}
+
+impl ApiScope {
+    /// The scope's name as stored in `api_keys`.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::ReadDrafts => "read-drafts",
+            ApiScope::WritePosts => "write-posts",
+            ApiScope::DeletePosts => "delete-posts",
+            ApiScope::Admin => "admin",
+        }
+    }
+}
 
-use crate::{BoxBody, SERVER_API_KEY};
+/// SHA-256 hash of the server key once it's been checked against its Argon2 hash.
+static VERIFIED_SERVER_KEY: OnceLock<String> = OnceLock::new();
+
+/// The outcome of checking a request's API key for a scope.
+enum KeyCheck {
+    Allowed,
+    MissingScope,
+    Invalid,
+}
 
 pub(crate) fn full<T: Into<Bytes>>(chunk: T) -> BoxBody {
     Full::new(chunk.into())
@@ -13,25 +61,305 @@ pub(crate) fn full<T: Into<Bytes>>(chunk: T) -> BoxBody {
         .boxed()
 }
 
-pub(crate) fn api_key_auth(req: &Request<Incoming>) -> bool {
-    if let Some(key) = req.headers().get("Authorization") {
-        return sha256_string(key)
-            == *SERVER_API_KEY
-                .get()
-                .expect("Error getting server API key from OnceLock");
+/// Checks the request's API key has the scope, e.g. to decide whether drafts are shown.
+pub(crate) async fn api_key_auth(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    scope: ApiScope,
+) -> bool {
+    matches!(
+        check_api_key(db, req, scope, false).await,
+        KeyCheck::Allowed
+    )
+}
+
+/// Checks the request's API key has the scope for handlers that require it. Returns the
+/// response to send if it doesn't: 401 without a valid key, or 403 if the key lacks the scope.
+pub(crate) async fn require_scope(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    scope: ApiScope,
+) -> Option<Response<BoxBody>> {
+    match check_api_key(db, req, scope, true).await {
+        KeyCheck::Allowed => None,
+        KeyCheck::MissingScope => Some(
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(full(format!(
+                    "Forbidden: Requires API key with scope {}",
+                    scope.as_str()
+                )))
+                .unwrap(),
+        ),
+        KeyCheck::Invalid => Some(
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header("WWW-Authenticate", "Bearer")
+                .body(full(b"Unauthorized: Requires API key".as_slice()))
+                .unwrap(),
+        ),
+    }
+}
+
+/// The key in `LS_API_KEY` has every scope, and is stored as an Argon2id hash or, from before
+/// those were supported, a SHA-256 one. Keys in `api_keys` have the scopes they were made
+/// with until they expire or are revoked, and record when they were last used. They're random,
+/// so a SHA-256 hash is enough to keep them safe and can be looked up directly.
+///
+/// Argon2 is slow enough that anyone could tie up the server sending bogus keys, so it's only
+/// run when `verify_argon2_key` is set, i.e. for handlers that require a key. Once the server
+/// key has been verified its SHA-256 hash is kept in `VERIFIED_SERVER_KEY`, and later checks,
+/// including those that only pick between draft and public views, compare against that.
+async fn check_api_key(
+    db: &DatabaseConnection,
+    req: &Request<Incoming>,
+    scope: ApiScope,
+    verify_argon2_key: bool,
+) -> KeyCheck {
+    let Some(key) = request_api_key(req) else {
+        return KeyCheck::Invalid;
+    };
+    let key_hash = sha256_string(key);
+    let server_key = SERVER_API_KEY
+        .get()
+        .expect("Error getting server API key from OnceLock");
+    // A legacy SHA-256 server key is cheap to check, so it's checked before the database. An
+    // Argon2 one is only checked once the key isn't found there.
+    let server_key_is_argon2 = is_argon2_hash(server_key);
+    if !server_key_is_argon2 && bool::from(key_hash.as_bytes().ct_eq(server_key.as_bytes())) {
+        return KeyCheck::Allowed;
+    }
+    if let Some(verified) = VERIFIED_SERVER_KEY.get()
+        && bool::from(key_hash.as_bytes().ct_eq(verified.as_bytes()))
+    {
+        return KeyCheck::Allowed;
+    }
+    let now = FixedOffset::east_opt(0)
+        .unwrap()
+        .from_utc_datetime(&Utc::now().naive_utc());
+    let api_key = match ApiKeyEntity::find()
+        .filter(ApiKeyColumn::KeyHash.eq(&key_hash))
+        .filter(ApiKeyColumn::RevokedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(ApiKeyColumn::ExpiresAt.is_null())
+                .add(ApiKeyColumn::ExpiresAt.gt(now)),
+        )
+        .one(db)
+        .await
+    {
+        Ok(Some(k)) => k,
+        Ok(None) => {
+            return if server_key_is_argon2
+                && verify_argon2_key
+                && verify_argon2(server_key, key).await
+            {
+                let _ = VERIFIED_SERVER_KEY.set(key_hash);
+                KeyCheck::Allowed
+            } else {
+                KeyCheck::Invalid
+            };
+        }
+        Err(e) => {
+            error!("{}", e);
+            return KeyCheck::Invalid;
+        }
+    };
+    if let Err(e) = ApiKeyEntity::update_many()
+        .col_expr(ApiKeyColumn::LastUsed, Expr::value(now))
+        .filter(ApiKeyColumn::Id.eq(api_key.id))
+        .exec(db)
+        .await
+    {
+        error!("{}", e);
+    }
+    if api_key
+        .scopes
+        .iter()
+        .any(|s| s == scope.as_str() || s == ApiScope::Admin.as_str())
+    {
+        KeyCheck::Allowed
+    } else {
+        KeyCheck::MissingScope
+    }
+}
+
+/// The API key sent with the request, either with the `Bearer` scheme or as the whole
+/// `Authorization` header.
+fn request_api_key(req: &Request<Incoming>) -> Option<&[u8]> {
+    let value = req.headers().get(AUTHORIZATION)?.as_bytes();
+    match value.split_at_checked(7) {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case(b"Bearer ") => {
+            Some(token.trim_ascii())
+        }
+        _ => Some(value),
+    }
+}
+
+/// Checks `LS_API_KEY` is an Argon2id hash in PHC string format or a hex SHA-256 hash, the
+/// latter lowercased to match `sha256_string`.
+pub(crate) fn parse_server_api_key(hash: &str) -> BoxResult<String> {
+    let hash = hash.trim();
+    if is_argon2_hash(hash) {
+        PasswordHash::new(hash)
+            .map_err(|e| anyhow!("LS_API_KEY isn't a valid Argon2 hash: {}", e))?;
+        return Ok(hash.to_owned());
+    }
+    if hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(hash.to_ascii_lowercase());
+    }
+
+    Err(anyhow!("LS_API_KEY should be an Argon2id hash or a hex SHA-256 hash").into())
+}
+
+/// Whether a hash is an Argon2id hash in PHC string format rather than a hex SHA-256 hash.
+/// Other Argon2 variants aren't accepted.
+fn is_argon2_hash(hash: &str) -> bool {
+    hash.starts_with("$argon2id$")
+}
+
+/// Checks a key against an Argon2 hash. Argon2 is deliberately slow, so it runs on the blocking
+/// thread pool rather than holding up other requests. The comparison is constant-time.
+async fn verify_argon2(hash: &'static str, key: &[u8]) -> bool {
+    let key = key.to_vec();
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(hash).is_ok_and(|h| Argon2::default().verify_password(&key, &h).is_ok())
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Formats a timestamp as an HTTP date for `Last-Modified`, e.g. "Fri, 16 Oct 2026 12:00:00 GMT".
+pub(crate) fn http_date(date: &DateTime<FixedOffset>) -> String {
+    date.with_timezone(&Utc)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Checks a GET request's `If-None-Match` and `If-Modified-Since` headers against the current
+/// validators. `If-None-Match` takes precedence when both are sent, and ETags are compared
+/// weakly since bodies are never served in ranges.
+pub(crate) fn is_not_modified<B>(
+    req: &Request<B>,
+    etag: &str,
+    last_modified: &DateTime<FixedOffset>,
+) -> bool {
+    if let Some(if_none_match) = req.headers().get(IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+        let etag = etag.trim_start_matches("W/");
+        return if_none_match
+            .split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == "*" || t == etag);
+    }
+    if let Some(if_modified_since) = req.headers().get(IF_MODIFIED_SINCE)
+        && let Ok(if_modified_since) = if_modified_since.to_str()
+        && let Ok(since) = DateTime::parse_from_rfc2822(if_modified_since)
+    {
+        // HTTP dates only have whole seconds.
+        return last_modified.timestamp() <= since.timestamp();
     }
 
     false
 }
 
+/// Builds the 304 response for a conditional GET, repeating the validators and caching policy
+/// the full response would have had.
+pub(crate) fn not_modified(
+    etag: &str,
+    last_modified: &DateTime<FixedOffset>,
+    cache_control: &str,
+) -> Response<BoxBody> {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header("ETag", etag)
+        .header("Last-Modified", http_date(last_modified))
+        .header("Cache-Control", cache_control)
+        .header("Vary", "Authorization")
+        .body(full(Bytes::new()))
+        .unwrap()
+}
+
 #[inline]
-fn sha256_string(data: &HeaderValue) -> String {
+pub(crate) fn sha256_string(data: &[u8]) -> String {
     use sha2::{Digest, Sha256};
 
     let mut hasher = Sha256::new();
-    hasher.update(data.as_bytes());
+    hasher.update(data);
     let result = hasher.finalize();
     let string_result = format!("{:x}", result);
 
     string_result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(header: &str, value: &str) -> Request<()> {
+        Request::builder().header(header, value).body(()).unwrap()
+    }
+
+    fn last_modified() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-10-16T12:00:00.5Z").unwrap()
+    }
+
+    #[test]
+    fn not_modified_when_etag_matches() {
+        let req = request("If-None-Match", "\"a\", W/\"b\"");
+        assert!(is_not_modified(&req, "\"b\"", &last_modified()));
+        assert!(is_not_modified(&req, "W/\"a\"", &last_modified()));
+        assert!(!is_not_modified(&req, "\"c\"", &last_modified()));
+        assert!(is_not_modified(
+            &request("If-None-Match", "*"),
+            "\"c\"",
+            &last_modified()
+        ));
+    }
+
+    #[test]
+    fn etag_takes_precedence_over_date() {
+        let req = Request::builder()
+            .header("If-None-Match", "\"a\"")
+            .header("If-Modified-Since", "Fri, 16 Oct 2026 12:00:00 GMT")
+            .body(())
+            .unwrap();
+        assert!(!is_not_modified(&req, "\"b\"", &last_modified()));
+    }
+
+    #[test]
+    fn not_modified_since_date() {
+        let modified = |since| {
+            is_not_modified(
+                &request("If-Modified-Since", since),
+                "\"a\"",
+                &last_modified(),
+            )
+        };
+        assert!(modified("Fri, 16 Oct 2026 12:00:00 GMT"));
+        assert!(!modified("Fri, 16 Oct 2026 11:59:59 GMT"));
+        assert!(!modified("not a date"));
+        assert!(!is_not_modified(
+            &Request::new(()),
+            "\"a\"",
+            &last_modified()
+        ));
+    }
+
+    #[test]
+    fn parses_server_api_keys() {
+        let sha256 = "A".repeat(64);
+        assert_eq!(parse_server_api_key(&sha256).unwrap(), "a".repeat(64));
+        let argon2 = "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG";
+        assert_eq!(
+            parse_server_api_key(&format!(" {}\n", argon2)).unwrap(),
+            argon2
+        );
+        assert!(parse_server_api_key(&argon2.replace("RdescudvJCsgt3ub", "!!")).is_err());
+        assert!(parse_server_api_key(&argon2.replace("argon2id", "argon2i")).is_err());
+        assert!(parse_server_api_key(&"g".repeat(64)).is_err());
+        assert!(parse_server_api_key("").is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use log::warn;
+use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
+use pulldown_cmark_escape::{escape_href, escape_html};
+
+use crate::blog_atom::render_markdown_fragment;
+
+/// Elements the built-in media shortcodes produce that ammonia doesn't allow by default, and
+/// their attributes. Allowed through the sanitizer.
+pub(crate) const SHORTCODE_TAG_ATTRIBUTES: [(&str, &[&str]); 2] = [
+    ("audio", &["controls", "preload", "src"]),
+    ("video", &["controls", "poster", "preload", "src"]),
+];
+
+/// Something embedded in a post with a fenced block whose info string is the shortcode's name
+/// in braces, e.g.
+///
+/// ````markdown
+/// ```{figure} /images/cat.jpg
+/// :alt: A cat on a windowsill
+/// Our cat, *pictured* in 2024.
+/// ```
+/// ````
+///
+/// Blocks naming a shortcode that isn't registered are left as code blocks.
+pub(crate) trait Shortcode: Send + Sync {
+    /// Renders the block to HTML, or returns why it can't be, e.g. a missing argument. Blocks
+    /// that fail to render are shown as they were written.
+    fn render(&self, args: &ShortcodeArgs) -> Result<String, String>;
+}
+
+pub(crate) struct ShortcodeArgs {
+    /// The rest of the info string after the name, e.g. the image URL above.
+    pub(crate) argument: String,
+    /// `:key: value` lines at the start of the block.
+    pub(crate) options: HashMap<String, String>,
+    /// The rest of the block, usually markdown.
+    pub(crate) body: String,
+}
+
+impl ShortcodeArgs {
+    fn parse(argument: &str, content: &str) -> Self {
+        let mut options = HashMap::new();
+        let mut body = content;
+        while let Some(option) = body.strip_prefix(':')
+            && let Some((key, rest)) = option.split_once(':')
+            && !key.is_empty()
+            && !key.contains(char::is_whitespace)
+        {
+            let (value, remaining) = rest.split_once('\n').unwrap_or((rest, ""));
+            options.insert(key.to_ascii_lowercase(), value.trim().to_owned());
+            body = remaining;
+        }
+
+        ShortcodeArgs {
+            argument: argument.trim().to_owned(),
+            options,
+            body: body.to_owned(),
+        }
+    }
+
+    /// The body rendered as markdown.
+    pub(crate) fn body_html(&self) -> String {
+        render_markdown_fragment(&self.body)
+    }
+}
+
+/// Shortcodes by name.
+pub(crate) struct ShortcodeRegistry {
+    shortcodes: HashMap<String, Box<dyn Shortcode>>,
+}
+
+impl ShortcodeRegistry {
+    /// A registry with the built-in `figure`, `callout`, `video` and `audio` shortcodes.
+    pub(crate) fn with_builtins() -> Self {
+        let mut registry = ShortcodeRegistry {
+            shortcodes: HashMap::new(),
+        };
+        registry.register("figure", Figure);
+        registry.register("callout", Callout);
+        registry.register("video", Media { tag: "video" });
+        registry.register("audio", Media { tag: "audio" });
+
+        registry
+    }
+
+    /// Adds a shortcode, replacing any already registered under the same name.
+    pub(crate) fn register(&mut self, name: &str, shortcode: impl Shortcode + 'static) {
+        self.shortcodes
+            .insert(name.to_ascii_lowercase(), Box::new(shortcode));
+    }
+
+    fn get(&self, name: &str) -> Option<&dyn Shortcode> {
+        self.shortcodes.get(name).map(|s| s.as_ref())
+    }
+}
+
+static SHORTCODES: OnceLock<ShortcodeRegistry> = OnceLock::new();
+
+/// Sets the shortcodes posts can use. Run once at startup, before anything is rendered.
+pub(crate) fn set_shortcode_registry(registry: ShortcodeRegistry) {
+    SHORTCODES
+        .set(registry)
+        .map_err(drop)
+        .expect("Error writing shortcode registry");
+}
+
+/// Replaces the shortcode blocks in a markdown event stream with their HTML. Runs before code
+/// highlighting, which would otherwise take them for code.
+pub(crate) fn expand_shortcodes<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> impl Iterator<Item = Event<'a>> {
+    let registry = SHORTCODES.get_or_init(ShortcodeRegistry::with_builtins);
+    let mut shortcode: Option<(&dyn Shortcode, String, String, String)> = None;
+    events.filter_map(move |event| match event {
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+            let Some((name, argument)) = info
+                .strip_prefix('{')
+                .and_then(|i| i.split_once('}'))
+                .map(|(name, argument)| (name.trim().to_ascii_lowercase(), argument))
+            else {
+                return Some(event);
+            };
+            let Some(found) = registry.get(&name) else {
+                return Some(event);
+            };
+            shortcode = Some((found, name, argument.to_owned(), String::new()));
+            None
+        }
+        Event::Text(text) if shortcode.is_some() => {
+            if let Some((_, _, _, content)) = shortcode.as_mut() {
+                content.push_str(&text);
+            }
+            None
+        }
+        Event::End(TagEnd::CodeBlock) if shortcode.is_some() => {
+            let (found, name, argument, content) = shortcode.take()?;
+            let html = found
+                .render(&ShortcodeArgs::parse(&argument, &content))
+                .unwrap_or_else(|e| {
+                    warn!("Error rendering {} shortcode: {}", name, e);
+                    let source = format!("```{{{}}}{}\n{}```", name, argument, content);
+                    format!(
+                        "<pre class=\"shortcode-error\"><code>{}</code></pre>\n",
+                        escaped(&source)
+                    )
+                });
+            Some(Event::Html(html.into()))
+        }
+        e => Some(e),
+    })
+}
+
+fn escaped(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    // Writing to a String can't fail.
+    let _ = escape_html(&mut escaped, text);
+    escaped
+}
+
+fn escaped_href(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    let _ = escape_href(&mut escaped, url);
+    escaped
+}
+
+/// Wraps an element in a figure, with the shortcode's body as its caption if it has one.
+fn figure(element: &str, args: &ShortcodeArgs) -> String {
+    let caption = if args.body.trim().is_empty() {
+        String::new()
+    } else {
+        format!("<figcaption>{}</figcaption>", args.body_html())
+    };
+
+    format!("<figure>{}{}</figure>\n", element, caption)
+}
+
+/// An image with alt text and an optional caption. The argument is the image's URL.
+///
+/// ````markdown
+/// ```{figure} /images/cat.jpg
+/// :alt: A cat on a windowsill
+/// :title: Optional title
+/// Optional caption in *markdown*.
+/// ```
+/// ````
+struct Figure;
+
+impl Shortcode for Figure {
+    fn render(&self, args: &ShortcodeArgs) -> Result<String, String> {
+        if args.argument.is_empty() {
+            return Err("missing image URL".to_owned());
+        }
+        let alt = args.options.get("alt").map_or("", |a| a.as_str());
+        let title = args
+            .options
+            .get("title")
+            .map_or(String::new(), |t| format!(" title=\"{}\"", escaped(t)));
+        let img = format!(
+            "<img src=\"{}\" alt=\"{}\"{}>",
+            escaped_href(&args.argument),
+            escaped(alt),
+            title
+        );
+
+        Ok(figure(&img, args))
+    }
+}
+
+/// A highlighted box around some markdown. The argument is one of `note` (the default), `tip`,
+/// `warning` or `danger`, which sets its class and default title.
+///
+/// ````markdown
+/// ```{callout} warning
+/// :title: Optional title
+/// Back up your database *before* migrating.
+/// ```
+/// ````
+struct Callout;
+
+const CALLOUT_KINDS: [&str; 4] = ["note", "tip", "warning", "danger"];
+
+impl Shortcode for Callout {
+    fn render(&self, args: &ShortcodeArgs) -> Result<String, String> {
+        let kind = match args.argument.to_ascii_lowercase() {
+            k if k.is_empty() => "note".to_owned(),
+            k if CALLOUT_KINDS.contains(&k.as_str()) => k,
+            k => {
+                return Err(format!(
+                    "unknown callout \"{}\", expected one of: {}",
+                    k,
+                    CALLOUT_KINDS.join(", ")
+                ))
+            }
+        };
+        let title = match args.options.get("title") {
+            Some(t) => t.to_owned(),
+            None => kind[..1].to_ascii_uppercase() + &kind[1..],
+        };
+
+        Ok(format!(
+            "<aside class=\"callout callout-{}\"><p class=\"callout-title\">{}</p>\n{}</aside>\n",
+            kind,
+            escaped(&title),
+            args.body_html()
+        ))
+    }
+}
+
+/// A video or audio player for a file hosted alongside the blog, with an optional caption. The
+/// argument is the file's URL, which has to be local, i.e. a path without a scheme or host.
+/// Videos can also have a `poster` image.
+///
+/// ````markdown
+/// ```{video} /media/demo.mp4
+/// :poster: /media/demo.jpg
+/// Optional caption in *markdown*.
+/// ```
+/// ````
+struct Media {
+    tag: &'static str,
+}
+
+/// Whether a URL is a path on the same site, e.g. `/media/demo.mp4` or `demo.mp4`.
+fn is_local_url(url: &str) -> bool {
+    !url.is_empty()
+        && !url.starts_with("//")
+        && url
+            .split(['/', '?', '#'])
+            .next()
+            .is_none_or(|first| !first.contains(':'))
+}
+
+impl Shortcode for Media {
+    fn render(&self, args: &ShortcodeArgs) -> Result<String, String> {
+        if !is_local_url(&args.argument) {
+            return Err(format!("expected a local {} URL", self.tag));
+        }
+        let poster = match args.options.get("poster") {
+            Some(_) if self.tag != "video" => return Err("only videos have a poster".to_owned()),
+            Some(p) if !is_local_url(p) => return Err("expected a local poster URL".to_owned()),
+            Some(p) => format!(" poster=\"{}\"", escaped_href(p)),
+            None => String::new(),
+        };
+        let player = format!(
+            "<{tag} controls preload=\"metadata\" src=\"{}\"{}></{tag}>",
+            escaped_href(&args.argument),
+            poster,
+            tag = self.tag
+        );
+
+        Ok(figure(&player, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_options_and_body() {
+        let args = ShortcodeArgs::parse(
+            " /images/cat.jpg ",
+            ":alt: A cat\n:Class: wide \n:not an option: x\nOur cat.\n",
+        );
+        assert_eq!(args.argument, "/images/cat.jpg");
+        assert_eq!(args.options.len(), 2);
+        assert_eq!(args.options["alt"], "A cat");
+        assert_eq!(args.options["class"], "wide");
+        assert_eq!(args.body, ":not an option: x\nOur cat.\n");
+    }
+
+    #[test]
+    fn parses_block_without_options() {
+        let args = ShortcodeArgs::parse("", "Just a body: with a colon\n");
+        assert!(args.options.is_empty());
+        assert_eq!(args.body, "Just a body: with a colon\n");
+    }
+
+    #[test]
+    fn accepts_only_local_urls() {
+        assert!(is_local_url("/media/demo.mp4"));
+        assert!(is_local_url("demo.mp4"));
+        assert!(is_local_url("demo.mp4?t=1:00"));
+        assert!(!is_local_url(""));
+        assert!(!is_local_url("//example.com/demo.mp4"));
+        assert!(!is_local_url("https://example.com/demo.mp4"));
+        assert!(!is_local_url("javascript:alert(1)"));
+    }
+}
@@ -0,0 +1,143 @@
+use hyper::{body::Incoming, Method, Request, Response, StatusCode};
+use log::error;
+use percent_encoding::percent_decode_str;
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr},
+    ColumnTrait, Condition, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, Statement,
+};
+use serde::Serialize;
+
+use crate::blog_atom::generate_filtered_atom_feed;
+use crate::blog_service::BlogPostInfo;
+use crate::entity::blog_posts::{Column as BlogPostColumn, Entity as BlogPostEntity};
+use crate::{server::full, BoxBody, BoxResult, Context};
+
+/// Utility struct for our GET /tags handler that returns every tag in use with its post count.
+#[derive(FromQueryResult, Serialize)]
+struct TagCount {
+    tag: String,
+    post_count: i64,
+}
+
+/// Routing function for everything under /api/tags/[tag].
+pub(crate) async fn handle_tag_request(
+    ctx: &Context,
+    req: Request<Incoming>,
+) -> BoxResult<Response<BoxBody>> {
+    let path = req.uri().path().to_owned();
+    let path_vec = path.split("/").collect::<Vec<&str>>();
+    let tag = percent_decode_str(path_vec[3]).decode_utf8_lossy();
+    if tag.is_empty() {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(full(
+                b"Bad request: URL should be in format '/api/tags/[tag]'".as_slice(),
+            ))
+            .unwrap());
+    }
+    match (req.method(), &path_vec[4..]) {
+        (&Method::GET, []) => get_tag_posts(&ctx.db, &tag).await,
+        (&Method::GET, ["atom"]) => get_tag_atom(&ctx.db, &tag, &path).await,
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Content-Type", "text/plain")
+            .body(full(b"Not Found".as_slice()))
+            .unwrap()),
+    }
+}
+
+/// Handler function for GET /tags that returns every tag on a visible post with the number of
+/// visible posts carrying it, most used first.
+pub(crate) async fn get_tags(db: &DatabaseConnection) -> BoxResult<Response<BoxBody>> {
+    let tags_vec = match TagCount::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        r#"SELECT tag, COUNT(*) AS post_count
+            FROM blog_posts, unnest(blog_posts.tags) AS tag
+            WHERE blog_posts.visible
+            GROUP BY tag
+            ORDER BY post_count DESC, tag ASC"#,
+    ))
+    .all(db)
+    .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let json = serde_json::to_string(&tags_vec).expect("Error converting tag vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /tags/[tag] that returns every visible post carrying the tag,
+/// newest first.
+async fn get_tag_posts(db: &DatabaseConnection, tag: &str) -> BoxResult<Response<BoxBody>> {
+    let posts_vec = match BlogPostEntity::find()
+        .filter(BlogPostColumn::Visible.eq(true))
+        .filter(tag_condition(tag))
+        .order_by_desc(BlogPostColumn::Date)
+        .all(db)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Database error".as_slice()))
+                .unwrap());
+        }
+    };
+    let posts_info: Vec<BlogPostInfo> = posts_vec.into_iter().map(|p| p.into()).collect();
+    let json =
+        serde_json::to_string(&posts_info).expect("Error converting blog post info vec to JSON");
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(full(json))
+        .unwrap())
+}
+
+/// Handler function for GET /tags/[tag]/atom that returns an Atom feed of the posts carrying
+/// the tag. Generated on request rather than cached like the main feed.
+async fn get_tag_atom(
+    db: &DatabaseConnection,
+    tag: &str,
+    path: &str,
+) -> BoxResult<Response<BoxBody>> {
+    let feed = match generate_filtered_atom_feed(db, None, tag_condition(tag), Some(path)).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .body(full(b"Error generating Atom feed".as_slice()))
+                .unwrap());
+        }
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/atom+xml")
+        .body(full(feed.to_string()))
+        .unwrap())
+}
+
+fn tag_condition(tag: &str) -> Condition {
+    Condition::all().add(Expr::col(BlogPostColumn::Tags).contains(vec![tag.to_owned()]))
+}